@@ -1,3 +1,4 @@
+use aurenfox::error::AurenError;
 use aurenfox::framework::AurenFoxFramework;
 use aurenfox::glfwvulkan_agent::GLFWVulkanAgent;
 
@@ -5,16 +6,24 @@ fn program(_app: &AurenFoxFramework) {
     
 }
 
-fn main() {
-    let mut app = AurenFoxFramework::new(GLFWVulkanAgent::new());
+fn main() -> Result<(), AurenError> {
+    let mut app = AurenFoxFramework::new(GLFWVulkanAgent::new()?);
 
-    let main_window_id = app.create_window("AurenFox Window", 800, 600, Some(0));
+    let main_window_id = app.create_window("AurenFox Window", 800, 600, Some(0))?;
 
-    let _ = app.create_window("Another Window", 1024, 768, Some(1));
+    let another_window_id = app.create_window("Another Window", 1024, 768, Some(1))?;
 
-    
+    app.on_window_update(main_window_id, Box::new(|_app, _id| {
+        // Update code for the main window
+    }))?;
+
+    app.on_window_update(another_window_id, Box::new(|_app, _id| {
+        // Update code for the other window
+    }))?;
 
     app.run(Some(Box::new(|app_context| {
         program(app_context);
     })));
+
+    Ok(())
 }
\ No newline at end of file
@@ -0,0 +1,82 @@
+use crate::interfaces::WindowId;
+use ash::vk;
+use std::fmt;
+
+// Errors the framework and its backends report instead of panicking
+#[derive(Debug, Clone, PartialEq)]
+pub enum AurenError {
+    // The windowing system (e.g. GLFW) could not be initialised
+    WindowSystemInit(String),
+    // The backend refused to create a window with this title
+    WindowCreation(String),
+    DuplicateWindowId(usize),
+    // The requested window id is too far past the ids in use
+    WindowIdOutOfRange(usize),
+    // No window was ever created with this id
+    UnknownWindow(WindowId),
+    // The window behind this id was destroyed or closed
+    DeadWindowHandle(WindowId),
+    // The backend needs a window to reach the clipboard and has none
+    ClipboardUnavailable,
+    // The pixel data of a custom cursor doesn't match its size or hotspot
+    InvalidCursorImage(String),
+    // The backend rejected a set of SDL_GameControllerDB gamepad mappings
+    InvalidGamepadMappings,
+    // No monitor is connected at this index
+    MonitorNotFound(usize),
+    // The Vulkan library could not be found or loaded
+    MissingVulkanLoader(String),
+    // A Vulkan call failed, `action` describes what the framework was doing
+    Vulkan { action: String, result: vk::Result },
+    NoSuitableGpu,
+    // The GPU pinned by name, UUID or AURENFOX_DEVICE is missing or unsuitable
+    RequestedGpuUnavailable(String),
+    SurfaceLost,
+    // The surface offers no formats or present modes to build a swap chain from
+    SwapchainUnsupported,
+}
+
+impl AurenError {
+    pub fn vulkan(action: &str, result: vk::Result) -> Self {
+        if result == vk::Result::ERROR_SURFACE_LOST_KHR {
+            return AurenError::SurfaceLost;
+        }
+
+        AurenError::Vulkan {
+            action: action.to_string(),
+            result,
+        }
+    }
+}
+
+impl fmt::Display for AurenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AurenError::WindowSystemInit(reason) => write!(f, "Failed to initialise the window system: {}", reason),
+            AurenError::WindowCreation(title) => write!(f, "Failed to create window with title: '{}'", title),
+            AurenError::DuplicateWindowId(id) => write!(f, "Window ID {} is already in use!", id),
+            AurenError::WindowIdOutOfRange(id) => write!(f, "Window ID {} is out of range", id),
+            AurenError::UnknownWindow(id) => write!(f, "No window with ID {} exists", id),
+            AurenError::DeadWindowHandle(id) => write!(f, "Window ID {} refers to a destroyed window", id),
+            AurenError::ClipboardUnavailable => write!(f, "The clipboard can't be reached without a window"),
+            AurenError::InvalidCursorImage(reason) => write!(f, "Invalid cursor image: {}", reason),
+            AurenError::InvalidGamepadMappings => write!(f, "The gamepad mappings could not be parsed"),
+            AurenError::MonitorNotFound(index) => write!(f, "No monitor connected at index {}", index),
+            AurenError::MissingVulkanLoader(reason) => write!(f, "Vulkan library not found: {}", reason),
+            AurenError::Vulkan { action, result } => write!(f, "Failed to {}: {}", action, result),
+            AurenError::NoSuitableGpu => write!(f, "No suitable GPU found"),
+            AurenError::RequestedGpuUnavailable(preference) => write!(f, "The requested GPU ({}) is missing or unsuitable", preference),
+            AurenError::SurfaceLost => write!(f, "The window surface was lost"),
+            AurenError::SwapchainUnsupported => write!(f, "The window surface does not support a swap chain"),
+        }
+    }
+}
+
+impl std::error::Error for AurenError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            AurenError::Vulkan { result, .. } => Some(result),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,139 @@
+use crate::interfaces::{GamepadAxis, GamepadState};
+
+// Dead zones applied to the gamepad axes read through Input. Inside the dead zone an
+// axis reads 0.0, outside it the remaining range is rescaled to still reach 1.0.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GamepadDeadZones {
+    // Radial, applied to the length of each stick
+    stick: f32,
+    trigger: f32,
+}
+
+impl Default for GamepadDeadZones {
+    fn default() -> Self {
+        Self {
+            stick: 0.15,
+            trigger: 0.05,
+        }
+    }
+}
+
+impl GamepadDeadZones {
+    // Both are clamped to 0.0..=0.99, a dead zone of 1.0 would swallow the whole range
+    pub fn new(stick: f32, trigger: f32) -> Self {
+        Self {
+            stick: clamp_dead_zone(stick),
+            trigger: clamp_dead_zone(trigger),
+        }
+    }
+
+    pub fn stick(&self) -> f32 {
+        self.stick
+    }
+
+    pub fn trigger(&self) -> f32 {
+        self.trigger
+    }
+
+    pub(crate) fn apply(&self, state: &GamepadState) -> GamepadState {
+        let mut filtered = state.clone();
+
+        let sticks = [
+            (GamepadAxis::LeftX, GamepadAxis::LeftY),
+            (GamepadAxis::RightX, GamepadAxis::RightY),
+        ];
+        for (x_axis, y_axis) in sticks {
+            let (x, y) = (state.axis(x_axis), state.axis(y_axis));
+            let scale = radial_scale(x, y, self.stick);
+            filtered.axes[x_axis as usize] = (x * scale).clamp(-1.0, 1.0);
+            filtered.axes[y_axis as usize] = (y * scale).clamp(-1.0, 1.0);
+        }
+
+        for trigger in [GamepadAxis::LeftTrigger, GamepadAxis::RightTrigger] {
+            let value = state.axis(trigger);
+            filtered.axes[trigger as usize] = if value <= self.trigger {
+                0.0
+            } else {
+                ((value - self.trigger) / (1.0 - self.trigger)).min(1.0)
+            };
+        }
+
+        filtered
+    }
+}
+
+fn clamp_dead_zone(dead_zone: f32) -> f32 {
+    if dead_zone.is_nan() { 0.0 } else { dead_zone.clamp(0.0, 0.99) }
+}
+
+// Factor that maps a stick of this length onto the range outside the dead zone
+fn radial_scale(x: f32, y: f32, dead_zone: f32) -> f32 {
+    let length = (x * x + y * y).sqrt();
+    if length <= dead_zone {
+        return 0.0;
+    }
+
+    let rescaled = ((length - dead_zone) / (1.0 - dead_zone)).min(1.0);
+    rescaled / length
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gamepad(axes: &[(GamepadAxis, f32)]) -> GamepadState {
+        let mut state = GamepadState::default();
+        for &(axis, value) in axes {
+            state.axes[axis as usize] = value;
+        }
+        state
+    }
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-5
+    }
+
+    #[test]
+    fn new_clamps_the_dead_zones() {
+        let dead_zones = GamepadDeadZones::new(-1.0, 5.0);
+        assert_eq!((dead_zones.stick(), dead_zones.trigger()), (0.0, 0.99));
+
+        assert_eq!(GamepadDeadZones::new(f32::NAN, 0.2).stick(), 0.0);
+    }
+
+    #[test]
+    fn sticks_inside_the_dead_zone_read_zero() {
+        let state = gamepad(&[(GamepadAxis::LeftX, 0.1), (GamepadAxis::LeftY, -0.1)]);
+        let filtered = GamepadDeadZones::new(0.15, 0.0).apply(&state);
+
+        assert_eq!(filtered.axis(GamepadAxis::LeftX), 0.0);
+        assert_eq!(filtered.axis(GamepadAxis::LeftY), 0.0);
+    }
+
+    #[test]
+    fn sticks_are_rescaled_radially() {
+        let dead_zones = GamepadDeadZones::new(0.2, 0.0);
+
+        let halfway = dead_zones.apply(&gamepad(&[(GamepadAxis::RightX, 0.6)]));
+        assert!(close(halfway.axis(GamepadAxis::RightX), 0.5));
+        assert_eq!(halfway.axis(GamepadAxis::RightY), 0.0);
+
+        // The direction is kept, only the length changes
+        let diagonal = dead_zones.apply(&gamepad(&[(GamepadAxis::LeftX, 0.6), (GamepadAxis::LeftY, 0.8)]));
+        assert!(close(diagonal.axis(GamepadAxis::LeftX), 0.6));
+        assert!(close(diagonal.axis(GamepadAxis::LeftY), 0.8));
+
+        let full = dead_zones.apply(&gamepad(&[(GamepadAxis::LeftX, -1.0)]));
+        assert!(close(full.axis(GamepadAxis::LeftX), -1.0));
+    }
+
+    #[test]
+    fn triggers_are_rescaled_linearly() {
+        let dead_zones = GamepadDeadZones::new(0.0, 0.2);
+        let state = gamepad(&[(GamepadAxis::LeftTrigger, 0.1), (GamepadAxis::RightTrigger, 0.6)]);
+        let filtered = dead_zones.apply(&state);
+
+        assert_eq!(filtered.axis(GamepadAxis::LeftTrigger), 0.0);
+        assert!(close(filtered.axis(GamepadAxis::RightTrigger), 0.5));
+    }
+}
@@ -0,0 +1,430 @@
+use super::gamepad::GamepadDeadZones;
+use crate::interfaces::{AurenAction, AurenEvent, AurenKey, AurenModifiers, AurenMouseButton, WindowId};
+use crate::interfaces::{GamepadAxis, GamepadButton, GamepadState, JoystickState};
+use std::collections::{HashMap, HashSet};
+
+// Polled keyboard, mouse and gamepad state, rebuilt from the events of every frame.
+// Keys and buttons are tracked per window, the plain queries answer for any window.
+// Joysticks are addressed by their index and read once per frame.
+#[derive(Default)]
+pub struct Input {
+    keys_down: HashSet<(WindowId, AurenKey)>,
+    keys_pressed: HashSet<(WindowId, AurenKey)>,
+    keys_released: HashSet<(WindowId, AurenKey)>,
+
+    buttons_down: HashSet<(WindowId, AurenMouseButton)>,
+    buttons_pressed: HashSet<(WindowId, AurenMouseButton)>,
+    buttons_released: HashSet<(WindowId, AurenMouseButton)>,
+
+    cursor_positions: HashMap<WindowId, (f64, f64)>,
+    mouse_delta: (f64, f64),
+    scroll_delta: (f64, f64),
+    modifiers: AurenModifiers,
+    focused_window: Option<WindowId>,
+
+    // Characters typed during the frame, for all windows and per window
+    text: String,
+    window_text: HashMap<WindowId, String>,
+
+    joysticks: Vec<JoystickState>,
+    // Gamepad states with the dead zones applied, by joystick index
+    gamepads: HashMap<usize, GamepadState>,
+    previous_gamepad_buttons: HashMap<usize, [bool; 15]>,
+    dead_zones: GamepadDeadZones,
+}
+
+impl Input {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Clears the per frame state and applies the events of the new frame
+    pub fn update(&mut self, events: &[AurenEvent]) {
+        self.keys_pressed.clear();
+        self.keys_released.clear();
+        self.buttons_pressed.clear();
+        self.buttons_released.clear();
+        self.mouse_delta = (0.0, 0.0);
+        self.scroll_delta = (0.0, 0.0);
+        self.text.clear();
+        self.window_text.clear();
+
+        for event in events {
+            self.apply(event);
+        }
+    }
+
+    fn apply(&mut self, event: &AurenEvent) {
+        match *event {
+            AurenEvent::Key { window, key, action, modifiers, .. } => {
+                self.modifiers = modifiers;
+                match action {
+                    AurenAction::Press => {
+                        self.keys_down.insert((window, key));
+                        self.keys_pressed.insert((window, key));
+                    }
+                    AurenAction::Release => {
+                        self.keys_down.remove(&(window, key));
+                        self.keys_released.insert((window, key));
+                    }
+                    AurenAction::Repeat => {}
+                }
+            }
+            AurenEvent::Char { window, character, .. } => {
+                self.text.push(character);
+                self.window_text.entry(window).or_default().push(character);
+            }
+            AurenEvent::MouseButton { window, button, action, modifiers, .. } => {
+                self.modifiers = modifiers;
+                match action {
+                    AurenAction::Press => {
+                        self.buttons_down.insert((window, button));
+                        self.buttons_pressed.insert((window, button));
+                    }
+                    AurenAction::Release => {
+                        self.buttons_down.remove(&(window, button));
+                        self.buttons_released.insert((window, button));
+                    }
+                    AurenAction::Repeat => {}
+                }
+            }
+            AurenEvent::CursorMoved { window, x, y, .. } => {
+                if let Some((last_x, last_y)) = self.cursor_positions.insert(window, (x, y)) {
+                    self.mouse_delta.0 += x - last_x;
+                    self.mouse_delta.1 += y - last_y;
+                }
+            }
+            AurenEvent::Scroll { x, y, .. } => {
+                self.scroll_delta.0 += x;
+                self.scroll_delta.1 += y;
+            }
+            AurenEvent::Focused { window, focused, .. } => {
+                if focused {
+                    self.focused_window = Some(window);
+                } else {
+                    // The release events of an unfocused window never arrive
+                    self.release_window(window);
+                    if self.focused_window == Some(window) {
+                        self.focused_window = None;
+                    }
+                }
+            }
+            AurenEvent::CloseRequested { window, .. } => self.forget_window(window),
+            _ => {}
+        }
+    }
+
+    // Replaces the joystick state with the one read for the new frame
+    pub(crate) fn update_joysticks(&mut self, joysticks: Vec<JoystickState>) {
+        self.previous_gamepad_buttons = self.gamepads
+            .iter()
+            .map(|(&index, gamepad)| (index, gamepad.buttons))
+            .collect();
+
+        self.gamepads = joysticks
+            .iter()
+            .filter_map(|joystick| {
+                let gamepad = joystick.gamepad.as_ref()?;
+                Some((joystick.index, self.dead_zones.apply(gamepad)))
+            })
+            .collect();
+        self.joysticks = joysticks;
+    }
+
+    pub(crate) fn set_dead_zones(&mut self, dead_zones: GamepadDeadZones) {
+        self.dead_zones = dead_zones;
+    }
+
+    fn release_window(&mut self, window: WindowId) {
+        self.keys_down.retain(|&(id, _)| id != window);
+        self.buttons_down.retain(|&(id, _)| id != window);
+    }
+
+    // Drops everything known about a closed or destroyed window
+    pub(crate) fn forget_window(&mut self, window: WindowId) {
+        self.release_window(window);
+        self.cursor_positions.remove(&window);
+        if self.focused_window == Some(window) {
+            self.focused_window = None;
+        }
+    }
+
+    // Every window the input state still refers to
+    pub(crate) fn tracked_windows(&self) -> HashSet<WindowId> {
+        self.keys_down.iter().map(|&(id, _)| id)
+            .chain(self.buttons_down.iter().map(|&(id, _)| id))
+            .chain(self.cursor_positions.keys().copied())
+            .chain(self.focused_window)
+            .collect()
+    }
+
+    pub fn is_key_down(&self, key: AurenKey) -> bool {
+        self.keys_down.iter().any(|&(_, k)| k == key)
+    }
+
+    pub fn is_key_down_in(&self, window: WindowId, key: AurenKey) -> bool {
+        self.keys_down.contains(&(window, key))
+    }
+
+    pub fn was_key_pressed_this_frame(&self, key: AurenKey) -> bool {
+        self.keys_pressed.iter().any(|&(_, k)| k == key)
+    }
+
+    pub fn was_key_released_this_frame(&self, key: AurenKey) -> bool {
+        self.keys_released.iter().any(|&(_, k)| k == key)
+    }
+
+    pub fn is_mouse_button_down(&self, button: AurenMouseButton) -> bool {
+        self.buttons_down.iter().any(|&(_, b)| b == button)
+    }
+
+    pub fn is_mouse_button_down_in(&self, window: WindowId, button: AurenMouseButton) -> bool {
+        self.buttons_down.contains(&(window, button))
+    }
+
+    pub fn was_mouse_button_pressed_this_frame(&self, button: AurenMouseButton) -> bool {
+        self.buttons_pressed.iter().any(|&(_, b)| b == button)
+    }
+
+    pub fn was_mouse_button_released_this_frame(&self, button: AurenMouseButton) -> bool {
+        self.buttons_released.iter().any(|&(_, b)| b == button)
+    }
+
+    // Last known cursor position inside the window, None until the cursor moved over it
+    pub fn mouse_position(&self, window: WindowId) -> Option<(f64, f64)> {
+        self.cursor_positions.get(&window).copied()
+    }
+
+    pub fn mouse_delta(&self) -> (f64, f64) {
+        self.mouse_delta
+    }
+
+    pub fn scroll_delta(&self) -> (f64, f64) {
+        self.scroll_delta
+    }
+
+    pub fn modifiers(&self) -> AurenModifiers {
+        self.modifiers
+    }
+
+    pub fn focused_window(&self) -> Option<WindowId> {
+        self.focused_window
+    }
+
+    // Text typed during this frame in any window, in the order it was typed.
+    // Use this instead of key events to fill text fields.
+    pub fn text_input(&self) -> &str {
+        &self.text
+    }
+
+    pub fn text_input_in(&self, window: WindowId) -> &str {
+        self.window_text.get(&window).map_or("", String::as_str)
+    }
+
+    // Connected joysticks with their raw axes, buttons and hats
+    pub fn joysticks(&self) -> &[JoystickState] {
+        &self.joysticks
+    }
+
+    pub fn joystick(&self, index: usize) -> Option<&JoystickState> {
+        self.joysticks.iter().find(|j| j.index == index)
+    }
+
+    // Standard gamepad state of the joystick with the dead zones applied,
+    // None if it isn't connected or has no gamepad mapping
+    pub fn gamepad(&self, index: usize) -> Option<&GamepadState> {
+        self.gamepads.get(&index)
+    }
+
+    // Indices of the connected joysticks that have a gamepad mapping, lowest first
+    pub fn gamepad_indices(&self) -> Vec<usize> {
+        let mut indices: Vec<usize> = self.gamepads.keys().copied().collect();
+        indices.sort_unstable();
+        indices
+    }
+
+    pub fn is_gamepad_button_down(&self, index: usize, button: GamepadButton) -> bool {
+        self.gamepad(index).is_some_and(|g| g.button(button))
+    }
+
+    pub fn was_gamepad_button_pressed_this_frame(&self, index: usize, button: GamepadButton) -> bool {
+        self.is_gamepad_button_down(index, button) && !self.was_gamepad_button_down(index, button)
+    }
+
+    pub fn was_gamepad_button_released_this_frame(&self, index: usize, button: GamepadButton) -> bool {
+        !self.is_gamepad_button_down(index, button) && self.was_gamepad_button_down(index, button)
+    }
+
+    fn was_gamepad_button_down(&self, index: usize, button: GamepadButton) -> bool {
+        self.previous_gamepad_buttons.get(&index).is_some_and(|b| b[button as usize])
+    }
+
+    // 0.0 for gamepads that aren't connected
+    pub fn gamepad_axis(&self, index: usize, axis: GamepadAxis) -> f32 {
+        self.gamepad(index).map_or(0.0, |g| g.axis(axis))
+    }
+
+    pub fn gamepad_dead_zones(&self) -> GamepadDeadZones {
+        self.dead_zones
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interfaces::WindowIdAllocator;
+
+    fn windows() -> (WindowId, WindowId) {
+        let mut ids = WindowIdAllocator::new();
+        (ids.allocate(None).unwrap(), ids.allocate(None).unwrap())
+    }
+
+    fn key(window: WindowId, key: AurenKey, action: AurenAction) -> AurenEvent {
+        AurenEvent::Key { window, time: 0.0, key, scancode: 0, action, modifiers: AurenModifiers::default() }
+    }
+
+    fn button(window: WindowId, button: AurenMouseButton, action: AurenAction) -> AurenEvent {
+        AurenEvent::MouseButton { window, time: 0.0, button, action, modifiers: AurenModifiers::default() }
+    }
+
+    #[test]
+    fn key_edges_last_one_frame() {
+        let (window, _) = windows();
+        let mut input = Input::new();
+
+        input.update(&[key(window, AurenKey::W, AurenAction::Press)]);
+        assert!(input.is_key_down(AurenKey::W));
+        assert!(input.was_key_pressed_this_frame(AurenKey::W));
+
+        input.update(&[key(window, AurenKey::W, AurenAction::Repeat)]);
+        assert!(input.is_key_down(AurenKey::W));
+        assert!(!input.was_key_pressed_this_frame(AurenKey::W));
+
+        input.update(&[key(window, AurenKey::W, AurenAction::Release)]);
+        assert!(!input.is_key_down(AurenKey::W));
+        assert!(input.was_key_released_this_frame(AurenKey::W));
+
+        input.update(&[]);
+        assert!(!input.was_key_released_this_frame(AurenKey::W));
+    }
+
+    #[test]
+    fn keys_are_tracked_per_window() {
+        let (first, second) = windows();
+        let mut input = Input::new();
+
+        input.update(&[key(first, AurenKey::A, AurenAction::Press)]);
+        assert!(input.is_key_down_in(first, AurenKey::A));
+        assert!(!input.is_key_down_in(second, AurenKey::A));
+
+        input.update(&[AurenEvent::Focused { window: first, time: 0.0, focused: false }]);
+        assert!(!input.is_key_down(AurenKey::A));
+    }
+
+    #[test]
+    fn mouse_button_edges_and_deltas() {
+        let (window, _) = windows();
+        let mut input = Input::new();
+
+        input.update(&[
+            button(window, AurenMouseButton::Left, AurenAction::Press),
+            AurenEvent::CursorMoved { window, time: 0.0, x: 1.0, y: 1.0 },
+            AurenEvent::CursorMoved { window, time: 0.0, x: 4.0, y: 3.0 },
+            AurenEvent::Scroll { window, time: 0.0, x: 0.0, y: -1.0 },
+        ]);
+        assert!(input.was_mouse_button_pressed_this_frame(AurenMouseButton::Left));
+        assert!(input.is_mouse_button_down_in(window, AurenMouseButton::Left));
+        assert_eq!(input.mouse_delta(), (3.0, 2.0));
+        assert_eq!(input.scroll_delta(), (0.0, -1.0));
+        assert_eq!(input.mouse_position(window), Some((4.0, 3.0)));
+
+        input.update(&[button(window, AurenMouseButton::Left, AurenAction::Release)]);
+        assert!(input.was_mouse_button_released_this_frame(AurenMouseButton::Left));
+        assert!(!input.is_mouse_button_down(AurenMouseButton::Left));
+        assert_eq!(input.mouse_delta(), (0.0, 0.0));
+        assert_eq!(input.scroll_delta(), (0.0, 0.0));
+    }
+
+    #[test]
+    fn forget_window_drops_its_state() {
+        let (closed, open) = windows();
+        let mut input = Input::new();
+
+        input.update(&[
+            AurenEvent::Focused { window: closed, time: 0.0, focused: true },
+            key(closed, AurenKey::Space, AurenAction::Press),
+            button(closed, AurenMouseButton::Right, AurenAction::Press),
+            AurenEvent::CursorMoved { window: closed, time: 0.0, x: 2.0, y: 2.0 },
+            key(open, AurenKey::Space, AurenAction::Press),
+        ]);
+        input.forget_window(closed);
+
+        assert!(!input.is_key_down_in(closed, AurenKey::Space));
+        assert!(input.is_key_down_in(open, AurenKey::Space));
+        assert!(!input.is_mouse_button_down(AurenMouseButton::Right));
+        assert_eq!(input.mouse_position(closed), None);
+        assert_eq!(input.focused_window(), None);
+        assert_eq!(input.tracked_windows(), HashSet::from([open]));
+    }
+
+    fn joystick(index: usize, pressed: bool, left_x: f32) -> JoystickState {
+        let mut gamepad = GamepadState::default();
+        gamepad.buttons[GamepadButton::A as usize] = pressed;
+        gamepad.axes[GamepadAxis::LeftX as usize] = left_x;
+        JoystickState { index, gamepad: Some(gamepad), ..Default::default() }
+    }
+
+    #[test]
+    fn gamepad_button_edges_last_one_frame() {
+        let mut input = Input::new();
+
+        input.update_joysticks(vec![joystick(2, true, 0.0)]);
+        assert!(input.was_gamepad_button_pressed_this_frame(2, GamepadButton::A));
+
+        input.update_joysticks(vec![joystick(2, true, 0.0)]);
+        assert!(input.is_gamepad_button_down(2, GamepadButton::A));
+        assert!(!input.was_gamepad_button_pressed_this_frame(2, GamepadButton::A));
+
+        input.update_joysticks(vec![joystick(2, false, 0.0)]);
+        assert!(input.was_gamepad_button_released_this_frame(2, GamepadButton::A));
+
+        // Unplugging a gamepad with a held button releases it
+        input.update_joysticks(vec![joystick(2, true, 0.0)]);
+        input.update_joysticks(Vec::new());
+        assert!(input.was_gamepad_button_released_this_frame(2, GamepadButton::A));
+        assert_eq!(input.gamepad(2), None);
+    }
+
+    #[test]
+    fn gamepad_axes_use_the_dead_zones() {
+        let mut input = Input::new();
+
+        input.update_joysticks(vec![joystick(0, false, 0.1), JoystickState { index: 1, ..Default::default() }]);
+        assert_eq!(input.gamepad_axis(0, GamepadAxis::LeftX), 0.0);
+        assert_eq!(input.joystick(0).unwrap().gamepad.as_ref().unwrap().axis(GamepadAxis::LeftX), 0.1);
+        assert_eq!(input.gamepad_indices(), vec![0]);
+
+        input.set_dead_zones(GamepadDeadZones::new(0.0, 0.0));
+        input.update_joysticks(vec![joystick(0, false, 0.1)]);
+        assert_eq!(input.gamepad_axis(0, GamepadAxis::LeftX), 0.1);
+    }
+
+    fn typed(window: WindowId, character: char) -> AurenEvent {
+        AurenEvent::Char { window, time: 0.0, character, modifiers: AurenModifiers::default() }
+    }
+
+    #[test]
+    fn text_input_is_collected_per_frame() {
+        let (first, second) = windows();
+        let mut input = Input::new();
+
+        input.update(&[typed(first, 'h'), typed(first, 'é'), typed(second, '日')]);
+        assert_eq!(input.text_input(), "hé日");
+        assert_eq!(input.text_input_in(first), "hé");
+        assert_eq!(input.text_input_in(second), "日");
+
+        input.update(&[]);
+        assert_eq!(input.text_input(), "");
+        assert_eq!(input.text_input_in(first), "");
+    }
+}
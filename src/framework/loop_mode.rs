@@ -0,0 +1,44 @@
+use std::time::{Duration, Instant};
+
+// Below this the OS sleep is too coarse, the limiter yields instead
+const SPIN_THRESHOLD: Duration = Duration::from_millis(2);
+
+// How the run loop paces its frames
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum LoopMode {
+    // Runs frames as fast as possible
+    #[default]
+    Continuous,
+    // Sleeps at the end of each frame to stay at the given frames per second
+    Capped(f64),
+    // Blocks until an event arrives or a redraw was requested. The user code only
+    // runs for frames with events or redraw requests. With a timeout the loop
+    // still wakes up regularly to process queued window destruction.
+    Reactive(Option<Duration>),
+}
+
+impl LoopMode {
+    pub(crate) fn frame_budget(&self) -> Option<Duration> {
+        match *self {
+            LoopMode::Capped(fps) if fps > 0.0 && fps.is_finite() => Some(Duration::from_secs_f64(1.0 / fps)),
+            _ => None,
+        }
+    }
+}
+
+// Sleeps until the frame budget is used up, finishing the last moments by yielding
+pub(crate) fn sleep_until(deadline: Instant) {
+    loop {
+        let now = Instant::now();
+        if now >= deadline {
+            return;
+        }
+
+        let remaining = deadline - now;
+        if remaining > SPIN_THRESHOLD {
+            std::thread::sleep(remaining - SPIN_THRESHOLD);
+        } else {
+            std::thread::yield_now();
+        }
+    }
+}
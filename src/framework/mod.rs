@@ -1,8 +1,48 @@
-use crate::interfaces::{RHI};
-use std::{cell::RefCell};
+mod gamepad;
+mod input;
+mod loop_mode;
+mod plugin;
+mod time;
+mod timestep;
+mod window_hooks;
+
+use crate::error::AurenError;
+use crate::interfaces::{AurenEvent, Cursor, CursorMode, MonitorInfo, RHI, WindowDescriptor, WindowId, WindowMetrics, WindowMode, WindowState};
+use crate::logging::target;
+use std::{cell::RefCell, collections::HashMap, time::Instant};
+
+pub use gamepad::GamepadDeadZones;
+pub use input::Input;
+pub use loop_mode::LoopMode;
+pub use plugin::Plugin;
+pub use time::FrameTime;
+pub use timestep::FixedTimestep;
+pub use window_hooks::{WindowCallback, WindowEventCallback};
+
+use timestep::FixedStepper;
+use window_hooks::{WindowData, WindowHooks};
+
+pub type EventSubscriber = Box<dyn FnMut(&AurenEvent) + 'static>;
+pub type UserCode = Box<dyn FnMut(&mut AurenFoxFramework) + 'static>;
+// Receives the fixed step in seconds
+pub type FixedUpdate = Box<dyn FnMut(&mut AurenFoxFramework, f64) + 'static>;
+// Receives the alpha between the last two fixed updates
+pub type RenderFn = Box<dyn FnMut(&mut AurenFoxFramework, f64) + 'static>;
+
 pub struct AurenFoxFramework {
     pub backend: Box<dyn RHI>,
-    pub destroy_queue: std::cell::RefCell<Vec<usize>>,
+    pub destroy_queue: std::cell::RefCell<Vec<WindowId>>,
+    events: Vec<AurenEvent>,
+    input: Input,
+    time: FrameTime,
+    loop_mode: LoopMode,
+    redraw_requests: Vec<WindowId>,
+    redraws_this_frame: Vec<WindowId>,
+    event_subscribers: Vec<EventSubscriber>,
+    window_hooks: HashMap<WindowId, WindowHooks>,
+    window_data: WindowData,
+    plugins: Vec<Box<dyn Plugin>>,
+    plugins_started: bool,
 }
 
 impl AurenFoxFramework {
@@ -10,45 +50,455 @@ impl AurenFoxFramework {
         Self {
             backend: Box::new(backend_struct),
             destroy_queue: RefCell::new(Vec::new()),
+            events: Vec::new(),
+            input: Input::new(),
+            time: FrameTime::new(),
+            loop_mode: LoopMode::default(),
+            redraw_requests: Vec::new(),
+            redraws_this_frame: Vec::new(),
+            event_subscribers: Vec::new(),
+            window_hooks: HashMap::new(),
+            window_data: WindowData::default(),
+            plugins: Vec::new(),
+            plugins_started: false,
         }
     }
 
-    pub fn create_window(&mut self, title: &str, width: u32, height: u32, id: Option<usize>) -> Result<usize, String> {
-        return self.backend.create_window(title, width, height, id);
+    pub fn create_window(&mut self, title: &str, width: u32, height: u32, id: Option<usize>) -> Result<WindowId, AurenError> {
+        self.create_window_with(&WindowDescriptor::new(title, width, height), id)
     }
 
-    pub fn run(&mut self, mut user_code: Option<Box<dyn FnMut(&mut AurenFoxFramework) + 'static>>) {
-        while !self.backend.should_close() {
-            self.process_destroy_queue();
-            self.backend.start_frame();
+    pub fn create_window_with(&mut self, descriptor: &WindowDescriptor, id: Option<usize>) -> Result<WindowId, AurenError> {
+        self.backend.create_window(descriptor, id)
+    }
+
+    // Builds the plugin right away. Plugins added while the loop runs are started immediately.
+    pub fn add_plugin(&mut self, mut plugin: impl Plugin + 'static) {
+        log::debug!(target: target::FRAMEWORK, "Adding plugin {}", plugin.name());
+        plugin.build(self);
+        if self.plugins_started {
+            plugin.startup(self);
+        }
+        self.plugins.push(Box::new(plugin));
+    }
 
+    pub fn run(&mut self, mut user_code: Option<UserCode>) {
+        self.run_loop(|app| {
             if let Some(ref mut code) = user_code {
-                code(self);
+                code(app);
             }
-            
-            self.backend.end_frame();
+        });
+    }
+
+    // Runs fixed_update at the tick rate of the timestep, independent of the frame rate.
+    // render runs once per frame with the alpha to interpolate between the last two fixed updates.
+    pub fn run_fixed(
+        &mut self,
+        timestep: FixedTimestep,
+        mut fixed_update: FixedUpdate,
+        mut render: Option<RenderFn>,
+    ) {
+        let mut stepper = FixedStepper::new(timestep);
+        let step = timestep.step_seconds();
+
+        self.run_loop(|app| {
+            let (ticks, alpha) = stepper.advance(app.time.delta());
+            for _ in 0..ticks {
+                fixed_update(app, step);
+            }
+
+            if let Some(ref mut render) = render {
+                render(app, alpha);
+            }
+        });
+    }
+
+    // Runs every active frame for the window, before the code passed to run
+    pub fn on_window_update(&mut self, id: WindowId, callback: WindowCallback) -> Result<(), AurenError> {
+        self.backend.validate_window(id)?;
+        self.window_hooks.entry(id).or_default().update = Some(callback);
+        Ok(())
+    }
+
+    // Runs every active frame for the window, after the code passed to run
+    pub fn on_window_draw(&mut self, id: WindowId, callback: WindowCallback) -> Result<(), AurenError> {
+        self.backend.validate_window(id)?;
+        self.window_hooks.entry(id).or_default().draw = Some(callback);
+        Ok(())
+    }
+
+    // Runs for every event of the window, before its update callback
+    pub fn on_window_event(&mut self, id: WindowId, callback: WindowEventCallback) -> Result<(), AurenError> {
+        self.backend.validate_window(id)?;
+        self.window_hooks.entry(id).or_default().event = Some(callback);
+        Ok(())
+    }
+
+    // Attaches a value to the window, replacing and returning the previous value of the same type.
+    // The value is dropped once the window is destroyed or closed.
+    pub fn set_window_data<T: 'static>(&mut self, id: WindowId, value: T) -> Result<Option<T>, AurenError> {
+        self.backend.validate_window(id)?;
+        Ok(self.window_data.insert(id, value))
+    }
+
+    pub fn window_data<T: 'static>(&self, id: WindowId) -> Option<&T> {
+        self.window_data.get(id)
+    }
+
+    pub fn window_data_mut<T: 'static>(&mut self, id: WindowId) -> Option<&mut T> {
+        self.window_data.get_mut(id)
+    }
+
+    pub fn take_window_data<T: 'static>(&mut self, id: WindowId) -> Option<T> {
+        self.window_data.remove(id)
+    }
+
+    // Takes effect from the next frame on
+    pub fn set_loop_mode(&mut self, mode: LoopMode) {
+        self.loop_mode = mode;
+    }
+
+    pub fn loop_mode(&self) -> LoopMode {
+        self.loop_mode
+    }
+
+    // Makes the next frame run the user code, even in LoopMode::Reactive without new events
+    pub fn request_redraw(&mut self, id: WindowId) -> Result<(), AurenError> {
+        self.backend.validate_window(id)?;
+        if !self.redraw_requests.contains(&id) {
+            self.redraw_requests.push(id);
         }
+        Ok(())
+    }
+
+    // Whether a redraw of the window was requested for the current frame
+    pub fn is_redraw_requested(&self, id: WindowId) -> bool {
+        self.redraws_this_frame.contains(&id)
     }
 
-    pub fn queue_destroy(&self, id: usize) {
+    // The window is destroyed at the start of the next frame
+    pub fn queue_destroy(&self, id: WindowId) -> Result<(), AurenError> {
+        self.backend.validate_window(id)?;
         self.destroy_queue.borrow_mut().push(id);
+        Ok(())
+    }
+
+    pub fn assign_master(&mut self, id: WindowId) -> Result<(), AurenError> {
+        self.backend.assign_master(id)
+    }
+
+    // Whether the id still addresses a live window
+    pub fn is_window_alive(&self, id: WindowId) -> bool {
+        self.backend.validate_window(id).is_ok()
+    }
+
+    pub fn window_title(&self, id: WindowId) -> Result<String, AurenError> {
+        self.backend.window_title(id)
+    }
+
+    pub fn set_window_title(&mut self, id: WindowId, title: &str) -> Result<(), AurenError> {
+        self.backend.set_window_title(id, title)
+    }
+
+    // Size in screen coordinates, the unit of positions and cursor events
+    pub fn window_size(&self, id: WindowId) -> Result<(u32, u32), AurenError> {
+        self.backend.window_size(id)
+    }
+
+    // Size in pixels, differs from window_size on HiDPI displays
+    pub fn framebuffer_size(&self, id: WindowId) -> Result<(u32, u32), AurenError> {
+        self.backend.framebuffer_size(id)
+    }
+
+    pub fn content_scale(&self, id: WindowId) -> Result<(f32, f32), AurenError> {
+        self.backend.content_scale(id)
+    }
+
+    // Logical size, framebuffer size and content scale together, with conversion helpers
+    pub fn window_metrics(&self, id: WindowId) -> Result<WindowMetrics, AurenError> {
+        Ok(WindowMetrics {
+            logical_size: self.backend.window_size(id)?,
+            framebuffer_size: self.backend.framebuffer_size(id)?,
+            content_scale: self.backend.content_scale(id)?,
+        })
+    }
+
+    pub fn set_window_size(&mut self, id: WindowId, width: u32, height: u32) -> Result<(), AurenError> {
+        self.backend.set_window_size(id, width, height)
+    }
+
+    pub fn window_position(&self, id: WindowId) -> Result<(i32, i32), AurenError> {
+        self.backend.window_position(id)
+    }
+
+    pub fn set_window_position(&mut self, id: WindowId, x: i32, y: i32) -> Result<(), AurenError> {
+        self.backend.set_window_position(id, x, y)
+    }
+
+    // Minimized, maximized, visible and focused flags of the window
+    pub fn window_state(&self, id: WindowId) -> Result<WindowState, AurenError> {
+        self.backend.window_state(id)
+    }
+
+    pub fn minimize_window(&mut self, id: WindowId) -> Result<(), AurenError> {
+        self.backend.minimize_window(id)
+    }
+
+    pub fn maximize_window(&mut self, id: WindowId) -> Result<(), AurenError> {
+        self.backend.maximize_window(id)
+    }
+
+    // Undoes minimize_window and maximize_window
+    pub fn restore_window(&mut self, id: WindowId) -> Result<(), AurenError> {
+        self.backend.restore_window(id)
+    }
+
+    pub fn show_window(&mut self, id: WindowId) -> Result<(), AurenError> {
+        self.backend.show_window(id)
+    }
+
+    pub fn hide_window(&mut self, id: WindowId) -> Result<(), AurenError> {
+        self.backend.hide_window(id)
+    }
+
+    // Steals focus from other applications, prefer request_window_attention
+    pub fn focus_window(&mut self, id: WindowId) -> Result<(), AurenError> {
+        self.backend.focus_window(id)
+    }
+
+    // Highlights the window (e.g. flashing taskbar entry) without taking focus
+    pub fn request_window_attention(&mut self, id: WindowId) -> Result<(), AurenError> {
+        self.backend.request_window_attention(id)
+    }
+
+    pub fn cursor_mode(&self, id: WindowId) -> Result<CursorMode, AurenError> {
+        self.backend.cursor_mode(id)
+    }
+
+    // CursorMode::Captured hides and locks the cursor, input().mouse_delta() keeps reporting motion
+    pub fn set_cursor_mode(&mut self, id: WindowId, mode: CursorMode) -> Result<(), AurenError> {
+        self.backend.set_cursor_mode(id, mode)
     }
 
-    pub fn assign_master(&mut self, id: usize) {
-        self.backend.assign_master(id);
+    // A standard shape or a custom image, shown while the cursor is over the window
+    pub fn set_cursor(&mut self, id: WindowId, cursor: &Cursor) -> Result<(), AurenError> {
+        self.backend.set_cursor(id, cursor)
+    }
+
+    // Text on the system clipboard, None if it's empty or holds something else
+    pub fn get_clipboard_text(&self) -> Option<String> {
+        self.backend.get_clipboard_text()
+    }
+
+    pub fn set_clipboard_text(&mut self, text: &str) -> Result<(), AurenError> {
+        self.backend.set_clipboard_text(text)
+    }
+
+    // Connected monitors, the primary monitor first
+    pub fn monitors(&mut self) -> Vec<MonitorInfo> {
+        self.backend.monitors()
+    }
+
+    pub fn window_mode(&self, id: WindowId) -> Result<WindowMode, AurenError> {
+        self.backend.window_mode(id)
+    }
+
+    // Switches between windowed, borderless fullscreen and exclusive fullscreen
+    pub fn set_window_mode(&mut self, id: WindowId, mode: WindowMode) -> Result<(), AurenError> {
+        self.backend.set_window_mode(id, mode)
+    }
+
+    // Events received by the backend during the current frame, oldest first
+    pub fn events(&self) -> std::slice::Iter<'_, AurenEvent> {
+        self.events.iter()
+    }
+
+    // Subscribers are called for every event at the start of each frame, before the user code runs
+    pub fn subscribe(&mut self, handler: EventSubscriber) {
+        self.event_subscribers.push(handler);
+    }
+
+    // Keyboard and mouse state as of the start of the current frame
+    pub fn input(&self) -> &Input {
+        &self.input
+    }
+
+    // Applied to the gamepad axes read through input() from the next frame on
+    pub fn set_gamepad_dead_zones(&mut self, dead_zones: GamepadDeadZones) {
+        self.input.set_dead_zones(dead_zones);
+    }
+
+    // Adds or replaces gamepad mappings in the SDL_GameControllerDB format, one per line
+    pub fn update_gamepad_mappings(&mut self, mappings: &str) -> Result<(), AurenError> {
+        self.backend.update_gamepad_mappings(mappings)
+    }
+
+    // Delta time, elapsed time, frame index and frames per second of the current frame
+    pub fn time(&self) -> &FrameTime {
+        &self.time
+    }
+
+    fn run_loop(&mut self, mut frame: impl FnMut(&mut AurenFoxFramework)) {
+        if !self.plugins_started {
+            self.plugins_started = true;
+            self.run_plugins(false, |plugin, app| plugin.startup(app));
+        }
+
+        while !self.backend.should_close() {
+            self.wait_for_frame();
+            self.begin_frame();
+            // start_frame found nothing left to run, e.g. the master window closed
+            if self.backend.should_close() {
+                break;
+            }
+            self.run_plugins(false, |plugin, app| plugin.pre_frame(app));
+
+            if self.is_frame_active() {
+                self.dispatch_window_events();
+                self.run_window_callbacks(|hooks| &mut hooks.update);
+                frame(self);
+                self.run_window_callbacks(|hooks| &mut hooks.draw);
+            }
+
+            self.run_plugins(true, |plugin, app| plugin.post_frame(app));
+            self.finish_frame();
+        }
+
+        self.run_plugins(true, |plugin, app| plugin.shutdown(app));
+        self.plugins_started = false;
+    }
+
+    // The plugins are taken out while their hooks run, plugins added by a hook go after them
+    fn run_plugins(&mut self, reverse: bool, hook: fn(&mut dyn Plugin, &mut AurenFoxFramework)) {
+        if self.plugins.is_empty() {
+            return;
+        }
+
+        let mut plugins = std::mem::take(&mut self.plugins);
+        if reverse {
+            for plugin in plugins.iter_mut().rev() {
+                hook(plugin.as_mut(), self);
+            }
+        } else {
+            for plugin in plugins.iter_mut() {
+                hook(plugin.as_mut(), self);
+            }
+        }
+
+        plugins.append(&mut self.plugins);
+        self.plugins = plugins;
+    }
+
+    fn wait_for_frame(&mut self) {
+        if let LoopMode::Reactive(timeout) = self.loop_mode
+            && self.redraw_requests.is_empty()
+            && self.destroy_queue.borrow().is_empty()
+        {
+            self.backend.wait_events(timeout);
+        }
+    }
+
+    fn is_frame_active(&self) -> bool {
+        match self.loop_mode {
+            LoopMode::Reactive(_) => !self.events.is_empty() || !self.redraws_this_frame.is_empty(),
+            _ => true,
+        }
+    }
+
+    fn begin_frame(&mut self) {
+        self.process_destroy_queue();
+        self.backend.start_frame();
+        self.release_closed_windows();
+        self.time.begin_frame(Instant::now());
+        self.redraws_this_frame = std::mem::take(&mut self.redraw_requests);
+        self.collect_events();
+    }
+
+    fn finish_frame(&mut self) {
+        self.backend.end_frame();
+        self.time.end_frame(Instant::now());
+
+        if let Some(budget) = self.loop_mode.frame_budget() {
+            loop_mode::sleep_until(self.time.frame_started() + budget);
+        }
+    }
+
+    fn collect_events(&mut self) {
+        self.events = self.backend.drain_events();
+        self.input.update(&self.events);
+        self.input.update_joysticks(self.backend.poll_joysticks());
+        for event in &self.events {
+            for subscriber in &mut self.event_subscribers {
+                subscriber(event);
+            }
+        }
+    }
+
+    fn dispatch_window_events(&mut self) {
+        if self.window_hooks.is_empty() {
+            return;
+        }
+
+        // Indexed, so app.events() still returns the whole frame inside the callbacks
+        for index in 0..self.events.len() {
+            let Some(id) = self.events[index].window() else { continue };
+            let Some(mut callback) = self.window_hooks.get_mut(&id).and_then(|hooks| hooks.event.take()) else { continue };
+
+            let event = self.events[index].clone();
+            callback(self, id, &event);
+
+            // Put it back unless the callback replaced itself or the window is gone
+            if let Some(hooks) = self.window_hooks.get_mut(&id) {
+                hooks.event.get_or_insert(callback);
+            }
+        }
+    }
+
+    fn run_window_callbacks(&mut self, slot: fn(&mut WindowHooks) -> &mut Option<WindowCallback>) {
+        let mut ids: Vec<WindowId> = self.window_hooks.keys().copied().collect();
+        ids.sort_unstable();
+
+        for id in ids {
+            let Some(mut callback) = self.window_hooks.get_mut(&id).and_then(|hooks| slot(hooks).take()) else { continue };
+
+            callback(self, id);
+
+            if let Some(hooks) = self.window_hooks.get_mut(&id) {
+                slot(hooks).get_or_insert(callback);
+            }
+        }
+    }
+
+    // Drops the callbacks and data of windows the backend closed
+    fn release_closed_windows(&mut self) {
+        let backend = &self.backend;
+        self.window_hooks.retain(|&id, _| backend.validate_window(id).is_ok());
+        self.window_data.retain_windows(|id| backend.validate_window(id).is_ok());
+        for id in self.input.tracked_windows() {
+            if backend.validate_window(id).is_err() {
+                self.input.forget_window(id);
+            }
+        }
     }
 
     fn process_destroy_queue(&mut self) {
-        let targets: Vec<usize> = self.destroy_queue.borrow_mut().drain(..).collect();
+        let targets: Vec<WindowId> = self.destroy_queue.borrow_mut().drain(..).collect();
         for id in targets {
-            self.destroy(&id);
+            self.destroy(id);
         }
     }
 
-    fn destroy(&mut self, id: &usize) {
-        self.backend.destroy_window(*id);
+    fn destroy(&mut self, id: WindowId) {
+        log::debug!(target: target::FRAMEWORK, "Destroying window {}", id);
+        // The window may have been closed since it was queued
+        if let Err(err) = self.backend.destroy_window(id) {
+            log::debug!(target: target::FRAMEWORK, "Skipped destroying window {}: {}", id, err);
+        }
+
+        self.window_hooks.remove(&id);
+        self.window_data.retain_windows(|window| window != id);
+        self.input.forget_window(id);
     }
 
-    
-}
 
+}
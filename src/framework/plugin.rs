@@ -0,0 +1,28 @@
+use super::AurenFoxFramework;
+
+// A reusable piece of the framework (input mapping, audio, UI, diagnostics, ...).
+// Every hook is optional. build, startup and pre_frame run in the order the plugins
+// were added, post_frame and shutdown in reverse order, so a plugin added later is
+// nested inside the ones it depends on.
+pub trait Plugin {
+    // Used in log messages
+    fn name(&self) -> &str {
+        std::any::type_name::<Self>()
+    }
+
+    // Called once by add_plugin, e.g. to create windows or register event subscribers
+    fn build(&mut self, _app: &mut AurenFoxFramework) {}
+
+    // Called once when the run loop starts, before the first frame
+    fn startup(&mut self, _app: &mut AurenFoxFramework) {}
+
+    // Called every frame after backend.start_frame() and the events are collected,
+    // before the user code
+    fn pre_frame(&mut self, _app: &mut AurenFoxFramework) {}
+
+    // Called every frame after the user code, before backend.end_frame()
+    fn post_frame(&mut self, _app: &mut AurenFoxFramework) {}
+
+    // Called once after the run loop ended
+    fn shutdown(&mut self, _app: &mut AurenFoxFramework) {}
+}
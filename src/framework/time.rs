@@ -0,0 +1,104 @@
+use std::time::{Duration, Instant};
+
+// Weight of the newest frame in the smoothed frames per second
+const FPS_SMOOTHING: f64 = 0.1;
+
+// Timing of the run loop, updated around backend.start_frame() and end_frame()
+pub struct FrameTime {
+    started: Instant,
+    frame_started: Option<Instant>,
+
+    delta: Duration,
+    elapsed: Duration,
+    frame_duration: Duration,
+    frame_index: u64,
+    fps: f64,
+}
+
+impl Default for FrameTime {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FrameTime {
+    pub fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            frame_started: None,
+
+            delta: Duration::ZERO,
+            elapsed: Duration::ZERO,
+            frame_duration: Duration::ZERO,
+            frame_index: 0,
+            fps: 0.0,
+        }
+    }
+
+    pub(crate) fn begin_frame(&mut self, now: Instant) {
+        match self.frame_started {
+            Some(previous) => {
+                self.delta = now.saturating_duration_since(previous);
+                self.frame_index += 1;
+            }
+            // The first frame has no previous frame to measure against
+            None => self.started = now,
+        }
+
+        self.frame_started = Some(now);
+        self.elapsed = now.saturating_duration_since(self.started);
+
+        let seconds = self.delta.as_secs_f64();
+        if seconds > 0.0 {
+            let current = 1.0 / seconds;
+            self.fps = if self.fps == 0.0 {
+                current
+            } else {
+                self.fps + (current - self.fps) * FPS_SMOOTHING
+            };
+        }
+    }
+
+    pub(crate) fn end_frame(&mut self, now: Instant) {
+        if let Some(frame_started) = self.frame_started {
+            self.frame_duration = now.saturating_duration_since(frame_started);
+        }
+    }
+
+    pub(crate) fn frame_started(&self) -> Instant {
+        self.frame_started.unwrap_or(self.started)
+    }
+
+    // Time between the start of the previous frame and the start of this one
+    pub fn delta(&self) -> Duration {
+        self.delta
+    }
+
+    pub fn delta_seconds(&self) -> f32 {
+        self.delta.as_secs_f32()
+    }
+
+    // Time since the start of the first frame
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    pub fn elapsed_seconds(&self) -> f64 {
+        self.elapsed.as_secs_f64()
+    }
+
+    // Time the previous frame spent between start_frame and end_frame
+    pub fn frame_duration(&self) -> Duration {
+        self.frame_duration
+    }
+
+    // Starts at 0 for the first frame
+    pub fn frame_index(&self) -> u64 {
+        self.frame_index
+    }
+
+    // Frames per second, smoothed over the last frames
+    pub fn fps(&self) -> f64 {
+        self.fps
+    }
+}
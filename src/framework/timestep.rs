@@ -0,0 +1,136 @@
+use crate::logging::target;
+use std::time::Duration;
+
+// Settings for AurenFoxFramework::run_fixed
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FixedTimestep {
+    // Fixed updates per second
+    pub tick_rate: f64,
+    // Most fixed updates run in a single frame before the backlog is dropped
+    pub max_ticks_per_frame: u32,
+    // Longer frames (breakpoints, dragged windows) only count for this long
+    pub max_frame_time: Duration,
+}
+
+impl Default for FixedTimestep {
+    fn default() -> Self {
+        Self::new(60.0)
+    }
+}
+
+impl FixedTimestep {
+    pub fn new(tick_rate: f64) -> Self {
+        Self {
+            tick_rate,
+            max_ticks_per_frame: 8,
+            max_frame_time: Duration::from_millis(250),
+        }
+    }
+
+    pub fn with_max_ticks_per_frame(mut self, max_ticks: u32) -> Self {
+        self.max_ticks_per_frame = max_ticks;
+        self
+    }
+
+    pub fn with_max_frame_time(mut self, max_frame_time: Duration) -> Self {
+        self.max_frame_time = max_frame_time;
+        self
+    }
+
+    // Length of a single fixed update in seconds
+    pub fn step_seconds(&self) -> f64 {
+        1.0 / self.tick_rate
+    }
+}
+
+// Turns frame deltas into a number of fixed updates and an interpolation alpha
+pub(crate) struct FixedStepper {
+    timestep: FixedTimestep,
+    accumulator: f64,
+}
+
+impl FixedStepper {
+    pub(crate) fn new(timestep: FixedTimestep) -> Self {
+        Self {
+            timestep,
+            accumulator: 0.0,
+        }
+    }
+
+    // Returns how many fixed updates to run this frame and the alpha to render with
+    pub(crate) fn advance(&mut self, delta: Duration) -> (u32, f64) {
+        let step = self.timestep.step_seconds();
+        if !step.is_finite() || step <= 0.0 {
+            return (0, 0.0);
+        }
+
+        self.accumulator += delta.min(self.timestep.max_frame_time).as_secs_f64();
+
+        let mut ticks = 0;
+        while self.accumulator >= step && ticks < self.timestep.max_ticks_per_frame {
+            self.accumulator -= step;
+            ticks += 1;
+        }
+
+        // Catching up would take longer than the updates are worth, so the
+        // simulation slows down instead of falling further behind every frame
+        if self.accumulator >= step {
+            let dropped = (self.accumulator / step).floor();
+            log::debug!(target: target::FRAMEWORK, "Fixed update can't keep up, dropping {} ticks", dropped);
+            self.accumulator -= dropped * step;
+        }
+
+        (ticks, self.accumulator / step)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn accumulates_partial_steps() {
+        let mut stepper = FixedStepper::new(FixedTimestep::new(100.0));
+
+        let (ticks, alpha) = stepper.advance(ms(5));
+        assert_eq!(ticks, 0);
+        assert!((alpha - 0.5).abs() < 1e-9);
+
+        let (ticks, alpha) = stepper.advance(ms(25));
+        assert_eq!(ticks, 3);
+        assert!(alpha.abs() < 1e-9);
+    }
+
+    #[test]
+    fn drops_the_backlog_past_max_ticks() {
+        let timestep = FixedTimestep::new(100.0).with_max_ticks_per_frame(4);
+        let mut stepper = FixedStepper::new(timestep);
+
+        let (ticks, alpha) = stepper.advance(ms(105));
+        assert_eq!(ticks, 4);
+        assert!((0.0..1.0).contains(&alpha));
+
+        // The dropped ticks are not made up for later
+        assert_eq!(stepper.advance(ms(10)).0, 1);
+    }
+
+    #[test]
+    fn clamps_long_frames() {
+        let timestep = FixedTimestep::new(10.0).with_max_frame_time(ms(200)).with_max_ticks_per_frame(100);
+        let mut stepper = FixedStepper::new(timestep);
+
+        assert_eq!(stepper.advance(Duration::from_secs(10)).0, 2);
+    }
+
+    #[test]
+    fn invalid_tick_rates_never_tick() {
+        for rate in [0.0, -60.0, f64::NAN] {
+            let mut stepper = FixedStepper::new(FixedTimestep::new(rate));
+            assert_eq!(stepper.advance(ms(100)), (0, 0.0));
+        }
+    }
+}
@@ -0,0 +1,51 @@
+use super::AurenFoxFramework;
+use crate::interfaces::{AurenEvent, WindowId};
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+
+pub type WindowCallback = Box<dyn FnMut(&mut AurenFoxFramework, WindowId) + 'static>;
+pub type WindowEventCallback = Box<dyn FnMut(&mut AurenFoxFramework, WindowId, &AurenEvent) + 'static>;
+
+// Callbacks registered for one window. A callback is taken out while it runs,
+// so it can use the framework (and even replace itself) without aliasing.
+#[derive(Default)]
+pub(crate) struct WindowHooks {
+    pub update: Option<WindowCallback>,
+    pub draw: Option<WindowCallback>,
+    pub event: Option<WindowEventCallback>,
+}
+
+// User data attached to windows, at most one value per type and window
+#[derive(Default)]
+pub(crate) struct WindowData {
+    values: HashMap<(WindowId, TypeId), Box<dyn Any>>,
+}
+
+impl WindowData {
+    pub fn insert<T: 'static>(&mut self, id: WindowId, value: T) -> Option<T> {
+        self.values
+            .insert((id, TypeId::of::<T>()), Box::new(value))
+            .and_then(|previous| previous.downcast().ok())
+            .map(|previous| *previous)
+    }
+
+    pub fn get<T: 'static>(&self, id: WindowId) -> Option<&T> {
+        self.values.get(&(id, TypeId::of::<T>()))?.downcast_ref()
+    }
+
+    pub fn get_mut<T: 'static>(&mut self, id: WindowId) -> Option<&mut T> {
+        self.values.get_mut(&(id, TypeId::of::<T>()))?.downcast_mut()
+    }
+
+    pub fn remove<T: 'static>(&mut self, id: WindowId) -> Option<T> {
+        self.values
+            .remove(&(id, TypeId::of::<T>()))
+            .and_then(|value| value.downcast().ok())
+            .map(|value| *value)
+    }
+
+    // Drops every value of windows the predicate rejects
+    pub fn retain_windows(&mut self, mut keep: impl FnMut(WindowId) -> bool) {
+        self.values.retain(|&(id, _), _| keep(id));
+    }
+}
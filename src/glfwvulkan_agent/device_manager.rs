@@ -1,9 +1,16 @@
-use colored::*;
 use ash::{Instance, khr, vk::{self}};
+use crate::error::AurenError;
+use crate::logging::target;
+use super::device_selection::{select_device, DeviceReport, DeviceSelection};
+use super::queue_families::{DeviceQueues, QueueFamilies, QueueLayout};
 
 pub struct AurenDeviceManager {
     physical_device: Option<vk::PhysicalDevice>,
     logical_device: Option<ash::Device>,
+    swapchain_loader: Option<khr::swapchain::Device>,
+    queue_families: Option<QueueFamilies>,
+    queues: DeviceQueues,
+    report: DeviceReport,
 }
 
 impl AurenDeviceManager {
@@ -11,48 +18,79 @@ impl AurenDeviceManager {
         Self {
             physical_device: None,
             logical_device: None,
+            swapchain_loader: None,
+            queue_families: None,
+            queues: DeviceQueues::default(),
+            report: DeviceReport::default(),
         }
     }
 
-    pub fn new(instance: &Instance) -> Self {
-        let physical_device_list = unsafe {
-            instance.enumerate_physical_devices().unwrap()
-        };
-
-        let physical_device = physical_device_list.first().expect("No GPUs found");
+    pub fn new(
+        instance: &Instance,
+        selection: &DeviceSelection,
+        present_support: &dyn Fn(vk::PhysicalDevice, u32) -> bool,
+    ) -> Result<Self, AurenError> {
+        let (selected, report) = select_device(instance, selection, present_support)?;
+        let physical_device = selected.physical_device;
 
-        let _device_extensions = [
-            khr::swapchain::NAME.as_ptr() as *const i8,
-        ];
+        let device_extensions: Vec<_> = selection.extensions.iter().map(|name| name.as_ptr()).collect();
 
-        let _device_features = vk::PhysicalDeviceFeatures::default();
+        let queue_families = selected.queue_families;
+        let layout = QueueLayout::new(&queue_families, &selected.queue_family_properties);
 
-        let priorities = [1.0_f32];
+        let priorities: Vec<Vec<f32>> = layout.counts
+            .iter()
+            .map(|&(_, count)| vec![1.0_f32; count as usize])
+            .collect();
 
-        let queue_info = vk::DeviceQueueCreateInfo::default()
-            .queue_family_index(0)
-            .queue_priorities(&priorities);
+        let queue_infos: Vec<vk::DeviceQueueCreateInfo> = layout.counts
+            .iter()
+            .zip(&priorities)
+            .map(|(&(family, _), priorities)| {
+                vk::DeviceQueueCreateInfo::default()
+                    .queue_family_index(family)
+                    .queue_priorities(priorities)
+            })
+            .collect();
 
         let device_create_info = vk::DeviceCreateInfo::default()
-            .queue_create_infos(std::slice::from_ref(&queue_info));
+            .queue_create_infos(&queue_infos)
+            .enabled_extension_names(&device_extensions)
+            .enabled_features(&selection.features);
 
         let logical_device = unsafe {
-            instance.create_device(*physical_device, &device_create_info, None)
-                .unwrap_or_else(|_| {
-                    panic!("{}{}->{} {} {}", 
-                        "Auren".bright_cyan().bold(), 
-                        "Fox".bright_red(), 
-                        "Vulkan".on_red(), 
-                        "[err]".bright_white().on_red().bold(),
-                        "Couldn't create logical device"
-                    )
-                })
+            instance.create_device(physical_device, &device_create_info, None)
+                .map_err(|e| AurenError::vulkan("create logical device", e))?
         };
 
-        Self {
-            physical_device: Some(*physical_device),
+        let get_queue = |(family, index): (u32, u32)| unsafe { logical_device.get_device_queue(family, index) };
+        let queues = DeviceQueues {
+            graphics: get_queue(layout.graphics),
+            present: get_queue(layout.present),
+            compute: get_queue(layout.compute),
+            transfer: get_queue(layout.transfer),
+        };
+
+        let swapchain_loader = selection.extensions
+            .iter()
+            .any(|name| name.as_c_str() == khr::swapchain::NAME)
+            .then(|| khr::swapchain::Device::new(instance, &logical_device));
+
+        log::info!(target: target::VULKAN, "Logical device created");
+        log::debug!(
+            target: target::VULKAN,
+            "Queues (family, index): graphics {:?}, present {:?}, compute {:?}, transfer {:?}",
+            layout.graphics, layout.present, layout.compute, layout.transfer,
+        );
+
+        Ok(Self {
+            physical_device: Some(physical_device),
             logical_device: Some(logical_device),
-        }
+            swapchain_loader,
+            queue_families: Some(queue_families),
+            queues,
+            report,
+        })
     }
 
     #[allow(dead_code)]
@@ -60,13 +98,40 @@ impl AurenDeviceManager {
         false
     }
     
-    #[allow(dead_code)]
     pub fn get_physical_device(&self) -> vk::PhysicalDevice {
         self.physical_device.unwrap()
     }
 
-    #[allow(dead_code)]
+    // None when VK_KHR_swapchain was removed from the required extensions
+    pub fn get_swapchain_loader(&self) -> Option<&khr::swapchain::Device> {
+        self.swapchain_loader.as_ref()
+    }
+
+    // None while no device is created, e.g. during reinitialize
+    pub fn get_queue_families(&self) -> Option<QueueFamilies> {
+        self.queue_families
+    }
+
+    pub fn get_queues(&self) -> DeviceQueues {
+        self.queues
+    }
+
+    pub fn report(&self) -> &DeviceReport {
+        &self.report
+    }
+
     pub fn get_logical_device(&self) -> &ash::Device {
         self.logical_device.as_ref().unwrap()
     }
+}
+
+impl Drop for AurenDeviceManager {
+    fn drop(&mut self) {
+        if let Some(device) = self.logical_device.take() {
+            unsafe {
+                let _ = device.device_wait_idle();
+                device.destroy_device(None);
+            }
+        }
+    }
 }
\ No newline at end of file
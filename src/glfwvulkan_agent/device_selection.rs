@@ -0,0 +1,486 @@
+use ash::{Instance, khr, vk};
+use std::ffi::{CStr, CString};
+use std::fmt;
+use crate::error::AurenError;
+use crate::logging::target;
+use super::queue_families::QueueFamilies;
+
+// Overrides the preference of the app, e.g. AURENFOX_DEVICE=llvmpipe or a UUID
+pub const DEVICE_ENV_VAR: &str = "AURENFOX_DEVICE";
+
+// Which GPU the agent should use when more than one is suitable
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum DevicePreference {
+    // The suitable device with the highest score
+    #[default]
+    Auto,
+    // Case-insensitive part of the device name, e.g. "nvidia" or "llvmpipe"
+    Name(String),
+    // The deviceUUID reported by the driver, which tells identical cards apart and survives driver updates
+    Uuid([u8; 16]),
+}
+
+impl DevicePreference {
+    // Reads AURENFOX_DEVICE. 32 hex digits (dashes allowed) are a UUID, anything else a name.
+    pub fn from_env() -> Option<Self> {
+        let value = std::env::var(DEVICE_ENV_VAR).ok()?;
+        let value = value.trim();
+        if value.is_empty() {
+            return None;
+        }
+
+        Some(parse_uuid(value).map(DevicePreference::Uuid).unwrap_or_else(|| DevicePreference::Name(value.to_string())))
+    }
+
+    fn matches(&self, name: &str, uuid: &[u8; 16]) -> bool {
+        match self {
+            DevicePreference::Auto => true,
+            DevicePreference::Name(part) => name.to_lowercase().contains(&part.to_lowercase()),
+            DevicePreference::Uuid(wanted) => wanted == uuid,
+        }
+    }
+}
+
+impl fmt::Display for DevicePreference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DevicePreference::Auto => write!(f, "automatic"),
+            DevicePreference::Name(part) => write!(f, "name containing '{}'", part),
+            DevicePreference::Uuid(uuid) => write!(f, "UUID {}", format_uuid(uuid)),
+        }
+    }
+}
+
+// What a GPU has to offer to be picked, and which one to prefer
+#[derive(Debug, Clone)]
+pub struct DeviceSelection {
+    pub preference: DevicePreference,
+    // Device extensions every candidate must support, VK_KHR_swapchain by default
+    pub extensions: Vec<CString>,
+    pub features: vk::PhysicalDeviceFeatures,
+    // Reject devices that can't present to the windows of the platform
+    pub presentation: bool,
+}
+
+impl Default for DeviceSelection {
+    fn default() -> Self {
+        Self {
+            preference: DevicePreference::Auto,
+            extensions: vec![khr::swapchain::NAME.to_owned()],
+            features: vk::PhysicalDeviceFeatures::default(),
+            presentation: true,
+        }
+    }
+}
+
+impl DeviceSelection {
+    pub fn prefer(mut self, preference: DevicePreference) -> Self {
+        self.preference = preference;
+        self
+    }
+
+    pub fn prefer_name(self, name: &str) -> Self {
+        self.prefer(DevicePreference::Name(name.to_string()))
+    }
+
+    pub fn prefer_uuid(self, uuid: [u8; 16]) -> Self {
+        self.prefer(DevicePreference::Uuid(uuid))
+    }
+
+    pub fn require_extension(mut self, extension: &CStr) -> Self {
+        if !self.extensions.iter().any(|e| e.as_c_str() == extension) {
+            self.extensions.push(extension.to_owned());
+        }
+        self
+    }
+
+    pub fn require_features(mut self, features: vk::PhysicalDeviceFeatures) -> Self {
+        self.features = features;
+        self
+    }
+
+    pub fn presentation(mut self, presentation: bool) -> Self {
+        self.presentation = presentation;
+        self
+    }
+}
+
+// One GPU as seen by the selection
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeviceCandidate {
+    pub name: String,
+    pub uuid: [u8; 16],
+    pub device_type: vk::PhysicalDeviceType,
+    // Size of the device local memory heaps in bytes
+    pub vram: u64,
+    pub score: u64,
+    // None when the device lacks a graphics queue or can't present
+    pub queue_families: Option<QueueFamilies>,
+    // Empty when the device meets every requirement
+    pub rejections: Vec<String>,
+    pub selected: bool,
+}
+
+impl DeviceCandidate {
+    pub fn is_suitable(&self) -> bool {
+        self.rejections.is_empty()
+    }
+}
+
+// Why each GPU was accepted or rejected, kept by the agent after start up
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct DeviceReport {
+    pub preference: DevicePreference,
+    pub candidates: Vec<DeviceCandidate>,
+}
+
+impl DeviceReport {
+    pub fn selected(&self) -> Option<&DeviceCandidate> {
+        self.candidates.iter().find(|c| c.selected)
+    }
+}
+
+impl fmt::Display for DeviceReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "GPU selection ({}):", self.preference)?;
+        for candidate in &self.candidates {
+            let verdict = if candidate.selected {
+                "selected"
+            } else if candidate.is_suitable() {
+                "suitable"
+            } else {
+                "rejected"
+            };
+
+            writeln!(
+                f,
+                "  {} [{:?}, {} MiB, UUID {}] score {}: {}",
+                candidate.name,
+                candidate.device_type,
+                candidate.vram / (1024 * 1024),
+                format_uuid(&candidate.uuid),
+                candidate.score,
+                verdict,
+            )?;
+            if let Some(queues) = candidate.queue_families {
+                writeln!(
+                    f,
+                    "    queue families: graphics {}, present {}, compute {}, transfer {}",
+                    queues.graphics, queues.present, queues.compute, queues.transfer,
+                )?;
+            }
+            for reason in &candidate.rejections {
+                writeln!(f, "    - {}", reason)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// The chosen device and the queue families it will use
+pub(crate) struct SelectedDevice {
+    pub physical_device: vk::PhysicalDevice,
+    pub queue_families: QueueFamilies,
+    pub queue_family_properties: Vec<vk::QueueFamilyProperties>,
+}
+
+// Checks every GPU against the selection. `present_support` tells whether a queue family
+// of a device can present to the windows of the platform.
+pub(crate) fn select_device(
+    instance: &Instance,
+    selection: &DeviceSelection,
+    present_support: &dyn Fn(vk::PhysicalDevice, u32) -> bool,
+) -> Result<(SelectedDevice, DeviceReport), AurenError> {
+    let physical_devices = unsafe {
+        instance.enumerate_physical_devices()
+            .map_err(|e| AurenError::vulkan("enumerate physical devices", e))?
+    };
+
+    let preference = DevicePreference::from_env().unwrap_or_else(|| selection.preference.clone());
+
+    let mut report = DeviceReport {
+        preference: preference.clone(),
+        candidates: Vec::with_capacity(physical_devices.len()),
+    };
+
+    for &physical_device in &physical_devices {
+        let candidate = inspect_device(instance, physical_device, selection, &preference, present_support)?;
+        report.candidates.push(candidate);
+    }
+
+    let best = report.candidates
+        .iter()
+        .enumerate()
+        .filter(|(_, c)| c.is_suitable())
+        .max_by_key(|(_, c)| c.score)
+        .map(|(index, _)| index);
+
+    let Some(best) = best else {
+        log::error!(target: target::VULKAN, "{}", report);
+        return Err(match preference {
+            DevicePreference::Auto => AurenError::NoSuitableGpu,
+            _ => AurenError::RequestedGpuUnavailable(preference.to_string()),
+        });
+    };
+
+    report.candidates[best].selected = true;
+    log::info!(target: target::VULKAN, "{}", report);
+
+    let physical_device = physical_devices[best];
+    let Some(queue_families) = report.candidates[best].queue_families else {
+        return Err(AurenError::NoSuitableGpu);
+    };
+
+    Ok((
+        SelectedDevice {
+            physical_device,
+            queue_families,
+            queue_family_properties: unsafe { instance.get_physical_device_queue_family_properties(physical_device) },
+        },
+        report,
+    ))
+}
+
+fn inspect_device(
+    instance: &Instance,
+    physical_device: vk::PhysicalDevice,
+    selection: &DeviceSelection,
+    preference: &DevicePreference,
+    present_support: &dyn Fn(vk::PhysicalDevice, u32) -> bool,
+) -> Result<DeviceCandidate, AurenError> {
+    // The device UUID comes from VkPhysicalDeviceIDProperties, core since Vulkan 1.1
+    let mut id_properties = vk::PhysicalDeviceIDProperties::default();
+    let properties = {
+        let mut properties2 = vk::PhysicalDeviceProperties2::default().push_next(&mut id_properties);
+        unsafe { instance.get_physical_device_properties2(physical_device, &mut properties2) };
+        properties2.properties
+    };
+    let memory = unsafe { instance.get_physical_device_memory_properties(physical_device) };
+    let features = unsafe { instance.get_physical_device_features(physical_device) };
+    let families = unsafe { instance.get_physical_device_queue_family_properties(physical_device) };
+    let extensions = unsafe {
+        instance.enumerate_device_extension_properties(physical_device)
+            .map_err(|e| AurenError::vulkan("enumerate device extensions", e))?
+    };
+
+    let name = properties.device_name_as_c_str()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let uuid = id_properties.device_uuid;
+
+    let vram = memory.memory_heaps_as_slice()
+        .iter()
+        .filter(|heap| heap.flags.contains(vk::MemoryHeapFlags::DEVICE_LOCAL))
+        .map(|heap| heap.size)
+        .sum();
+
+    let mut rejections = Vec::new();
+
+    for required in &selection.extensions {
+        let supported = extensions.iter().any(|e| e.extension_name_as_c_str() == Ok(required.as_c_str()));
+        if !supported {
+            rejections.push(format!("missing extension {}", required.to_string_lossy()));
+        }
+    }
+
+    for (feature, required, available) in compare_features(&selection.features, &features) {
+        if required != vk::FALSE && available == vk::FALSE {
+            rejections.push(format!("missing feature {}", feature));
+        }
+    }
+
+    let has_graphics = families.iter().any(|family| family.queue_count > 0 && family.queue_flags.contains(vk::QueueFlags::GRAPHICS));
+    let queue_families = QueueFamilies::find(
+        &families,
+        |index| present_support(physical_device, index),
+        selection.presentation,
+    );
+
+    if !has_graphics {
+        rejections.push("no graphics queue".to_string());
+    } else if queue_families.is_none() {
+        rejections.push("no queue can present to a window".to_string());
+    }
+
+    if !preference.matches(&name, &uuid) {
+        rejections.push(format!("does not match the requested {}", preference));
+    }
+
+    let candidate = DeviceCandidate {
+        score: score(properties.device_type, vram),
+        name,
+        uuid,
+        device_type: properties.device_type,
+        vram,
+        queue_families,
+        rejections,
+        selected: false,
+    };
+
+    Ok(candidate)
+}
+
+// Device type first (discrete > integrated > virtual > CPU), then VRAM in MiB
+fn score(device_type: vk::PhysicalDeviceType, vram: u64) -> u64 {
+    let rank = match device_type {
+        vk::PhysicalDeviceType::DISCRETE_GPU => 4,
+        vk::PhysicalDeviceType::INTEGRATED_GPU => 3,
+        vk::PhysicalDeviceType::VIRTUAL_GPU => 2,
+        vk::PhysicalDeviceType::CPU => 1,
+        _ => 0,
+    };
+
+    rank * 1_000_000_000 + (vram / (1024 * 1024)).min(999_999_999)
+}
+
+fn parse_uuid(value: &str) -> Option<[u8; 16]> {
+    let hex: String = value.chars().filter(|&c| c != '-').collect();
+    if hex.len() != 32 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    let mut uuid = [0; 16];
+    for (i, byte) in uuid.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).ok()?;
+    }
+    Some(uuid)
+}
+
+fn format_uuid(uuid: &[u8; 16]) -> String {
+    let hex: Vec<String> = uuid.iter().map(|byte| format!("{:02x}", byte)).collect();
+    format!(
+        "{}-{}-{}-{}-{}",
+        hex[0..4].concat(),
+        hex[4..6].concat(),
+        hex[6..8].concat(),
+        hex[8..10].concat(),
+        hex[10..16].concat(),
+    )
+}
+
+// (name, required, available) for every Vulkan 1.0 feature
+macro_rules! feature_table {
+    ($required:expr, $available:expr, [$($feature:ident),* $(,)?]) => {
+        vec![$((stringify!($feature), $required.$feature, $available.$feature)),*]
+    };
+}
+
+fn compare_features(
+    required: &vk::PhysicalDeviceFeatures,
+    available: &vk::PhysicalDeviceFeatures,
+) -> Vec<(&'static str, vk::Bool32, vk::Bool32)> {
+    feature_table!(required, available, [
+        robust_buffer_access,
+        full_draw_index_uint32,
+        image_cube_array,
+        independent_blend,
+        geometry_shader,
+        tessellation_shader,
+        sample_rate_shading,
+        dual_src_blend,
+        logic_op,
+        multi_draw_indirect,
+        draw_indirect_first_instance,
+        depth_clamp,
+        depth_bias_clamp,
+        fill_mode_non_solid,
+        depth_bounds,
+        wide_lines,
+        large_points,
+        alpha_to_one,
+        multi_viewport,
+        sampler_anisotropy,
+        texture_compression_etc2,
+        texture_compression_astc_ldr,
+        texture_compression_bc,
+        occlusion_query_precise,
+        pipeline_statistics_query,
+        vertex_pipeline_stores_and_atomics,
+        fragment_stores_and_atomics,
+        shader_tessellation_and_geometry_point_size,
+        shader_image_gather_extended,
+        shader_storage_image_extended_formats,
+        shader_storage_image_multisample,
+        shader_storage_image_read_without_format,
+        shader_storage_image_write_without_format,
+        shader_uniform_buffer_array_dynamic_indexing,
+        shader_sampled_image_array_dynamic_indexing,
+        shader_storage_buffer_array_dynamic_indexing,
+        shader_storage_image_array_dynamic_indexing,
+        shader_clip_distance,
+        shader_cull_distance,
+        shader_float64,
+        shader_int64,
+        shader_int16,
+        shader_resource_residency,
+        shader_resource_min_lod,
+        sparse_binding,
+        sparse_residency_buffer,
+        sparse_residency_image2_d,
+        sparse_residency_image3_d,
+        sparse_residency2_samples,
+        sparse_residency4_samples,
+        sparse_residency8_samples,
+        sparse_residency16_samples,
+        sparse_residency_aliased,
+        variable_multisample_rate,
+        inherited_queries,
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UUID: [u8; 16] = [
+        0x12, 0x34, 0x56, 0x78, 0x9a, 0xbc, 0xde, 0xf0,
+        0x01, 0x23, 0x45, 0x67, 0x89, 0xab, 0xcd, 0xef,
+    ];
+
+    #[test]
+    fn parses_uuids_with_and_without_dashes() {
+        assert_eq!(parse_uuid("12345678-9abc-def0-0123-456789abcdef"), Some(UUID));
+        assert_eq!(parse_uuid("123456789ABCDEF00123456789ABCDEF"), Some(UUID));
+    }
+
+    #[test]
+    fn rejects_anything_else_as_uuid() {
+        assert_eq!(parse_uuid("llvmpipe"), None);
+        assert_eq!(parse_uuid("123456789abcdef00123456789abcde"), None);
+        assert_eq!(parse_uuid("123456789abcdef00123456789abcdefa"), None);
+        assert_eq!(parse_uuid("g23456789abcdef00123456789abcdef"), None);
+        // Not ASCII, must not slice through a character
+        assert_eq!(parse_uuid("é23456789abcdef00123456789abcde"), None);
+    }
+
+    #[test]
+    fn formats_uuids_in_8_4_4_4_12_groups() {
+        let formatted = format_uuid(&UUID);
+
+        assert_eq!(formatted, "12345678-9abc-def0-0123-456789abcdef");
+        assert_eq!(parse_uuid(&formatted), Some(UUID));
+    }
+
+    #[test]
+    fn score_ranks_by_type_then_vram() {
+        let gib = 1024 * 1024 * 1024;
+        let discrete = score(vk::PhysicalDeviceType::DISCRETE_GPU, gib);
+        let integrated = score(vk::PhysicalDeviceType::INTEGRATED_GPU, 64 * gib);
+
+        assert!(discrete > integrated);
+        assert!(integrated > score(vk::PhysicalDeviceType::VIRTUAL_GPU, 64 * gib));
+        assert!(score(vk::PhysicalDeviceType::CPU, 0) > score(vk::PhysicalDeviceType::OTHER, u64::MAX));
+        assert!(score(vk::PhysicalDeviceType::DISCRETE_GPU, 8 * gib) > discrete);
+        assert_eq!(score(vk::PhysicalDeviceType::OTHER, 0), 0);
+    }
+
+    #[test]
+    fn preferences_match_by_name_part_or_uuid() {
+        assert!(DevicePreference::Auto.matches("anything", &UUID));
+        assert!(DevicePreference::Name("NVIDIA".to_string()).matches("NVIDIA GeForce RTX", &UUID));
+        assert!(DevicePreference::Name("llvm".to_string()).matches("LLVMpipe (LLVM 17)", &UUID));
+        assert!(!DevicePreference::Name("amd".to_string()).matches("Intel UHD", &UUID));
+        assert!(DevicePreference::Uuid(UUID).matches("any", &UUID));
+        assert!(!DevicePreference::Uuid([0; 16]).matches("any", &UUID));
+    }
+}
@@ -0,0 +1,207 @@
+use crate::interfaces::{AurenAction, AurenEvent, AurenKey, AurenModifiers, AurenMouseButton, WindowId};
+
+// Converts a GLFW window event into the backend neutral AurenEvent.
+// Events the framework does not expose yet return None.
+pub fn translate_event(window: WindowId, time: f64, event: &glfw::WindowEvent) -> Option<AurenEvent> {
+    match *event {
+        glfw::WindowEvent::Key(key, scancode, action, mods) => Some(AurenEvent::Key {
+            window,
+            time,
+            key: translate_key(key),
+            scancode,
+            action: translate_action(action),
+            modifiers: translate_modifiers(mods),
+        }),
+        glfw::WindowEvent::Char(character) => Some(AurenEvent::Char {
+            window,
+            time,
+            character,
+            modifiers: AurenModifiers::default(),
+        }),
+        glfw::WindowEvent::CharModifiers(character, mods) => Some(AurenEvent::Char {
+            window,
+            time,
+            character,
+            modifiers: translate_modifiers(mods),
+        }),
+        glfw::WindowEvent::MouseButton(button, action, mods) => Some(AurenEvent::MouseButton {
+            window,
+            time,
+            button: translate_mouse_button(button),
+            action: translate_action(action),
+            modifiers: translate_modifiers(mods),
+        }),
+        glfw::WindowEvent::CursorPos(x, y) => Some(AurenEvent::CursorMoved { window, time, x, y }),
+        glfw::WindowEvent::Scroll(x, y) => Some(AurenEvent::Scroll { window, time, x, y }),
+        glfw::WindowEvent::Focus(focused) => Some(AurenEvent::Focused { window, time, focused }),
+        glfw::WindowEvent::Size(w, h) => Some(AurenEvent::Resized {
+            window,
+            time,
+            width: w.max(0) as u32,
+            height: h.max(0) as u32,
+        }),
+        glfw::WindowEvent::FramebufferSize(w, h) => Some(AurenEvent::FramebufferResized {
+            window,
+            time,
+            width: w.max(0) as u32,
+            height: h.max(0) as u32,
+        }),
+        glfw::WindowEvent::ContentScale(x_scale, y_scale) => Some(AurenEvent::ScaleChanged { window, time, x_scale, y_scale }),
+        glfw::WindowEvent::Close => Some(AurenEvent::CloseRequested { window, time }),
+        _ => None,
+    }
+}
+
+pub fn translate_action(action: glfw::Action) -> AurenAction {
+    match action {
+        glfw::Action::Press => AurenAction::Press,
+        glfw::Action::Release => AurenAction::Release,
+        glfw::Action::Repeat => AurenAction::Repeat,
+    }
+}
+
+pub fn translate_modifiers(mods: glfw::Modifiers) -> AurenModifiers {
+    AurenModifiers {
+        shift: mods.contains(glfw::Modifiers::Shift),
+        control: mods.contains(glfw::Modifiers::Control),
+        alt: mods.contains(glfw::Modifiers::Alt),
+        super_key: mods.contains(glfw::Modifiers::Super),
+        caps_lock: mods.contains(glfw::Modifiers::CapsLock),
+        num_lock: mods.contains(glfw::Modifiers::NumLock),
+    }
+}
+
+pub fn translate_mouse_button(button: glfw::MouseButton) -> AurenMouseButton {
+    match button {
+        glfw::MouseButton::Button1 => AurenMouseButton::Left,
+        glfw::MouseButton::Button2 => AurenMouseButton::Right,
+        glfw::MouseButton::Button3 => AurenMouseButton::Middle,
+        other => AurenMouseButton::Other(other as u8),
+    }
+}
+
+pub fn translate_key(key: glfw::Key) -> AurenKey {
+    match key {
+        glfw::Key::Space => AurenKey::Space,
+        glfw::Key::Apostrophe => AurenKey::Apostrophe,
+        glfw::Key::Comma => AurenKey::Comma,
+        glfw::Key::Minus => AurenKey::Minus,
+        glfw::Key::Period => AurenKey::Period,
+        glfw::Key::Slash => AurenKey::Slash,
+        glfw::Key::Num0 => AurenKey::Num0,
+        glfw::Key::Num1 => AurenKey::Num1,
+        glfw::Key::Num2 => AurenKey::Num2,
+        glfw::Key::Num3 => AurenKey::Num3,
+        glfw::Key::Num4 => AurenKey::Num4,
+        glfw::Key::Num5 => AurenKey::Num5,
+        glfw::Key::Num6 => AurenKey::Num6,
+        glfw::Key::Num7 => AurenKey::Num7,
+        glfw::Key::Num8 => AurenKey::Num8,
+        glfw::Key::Num9 => AurenKey::Num9,
+        glfw::Key::Semicolon => AurenKey::Semicolon,
+        glfw::Key::Equal => AurenKey::Equal,
+        glfw::Key::A => AurenKey::A,
+        glfw::Key::B => AurenKey::B,
+        glfw::Key::C => AurenKey::C,
+        glfw::Key::D => AurenKey::D,
+        glfw::Key::E => AurenKey::E,
+        glfw::Key::F => AurenKey::F,
+        glfw::Key::G => AurenKey::G,
+        glfw::Key::H => AurenKey::H,
+        glfw::Key::I => AurenKey::I,
+        glfw::Key::J => AurenKey::J,
+        glfw::Key::K => AurenKey::K,
+        glfw::Key::L => AurenKey::L,
+        glfw::Key::M => AurenKey::M,
+        glfw::Key::N => AurenKey::N,
+        glfw::Key::O => AurenKey::O,
+        glfw::Key::P => AurenKey::P,
+        glfw::Key::Q => AurenKey::Q,
+        glfw::Key::R => AurenKey::R,
+        glfw::Key::S => AurenKey::S,
+        glfw::Key::T => AurenKey::T,
+        glfw::Key::U => AurenKey::U,
+        glfw::Key::V => AurenKey::V,
+        glfw::Key::W => AurenKey::W,
+        glfw::Key::X => AurenKey::X,
+        glfw::Key::Y => AurenKey::Y,
+        glfw::Key::Z => AurenKey::Z,
+        glfw::Key::LeftBracket => AurenKey::LeftBracket,
+        glfw::Key::Backslash => AurenKey::Backslash,
+        glfw::Key::RightBracket => AurenKey::RightBracket,
+        glfw::Key::GraveAccent => AurenKey::GraveAccent,
+        glfw::Key::World1 => AurenKey::World1,
+        glfw::Key::World2 => AurenKey::World2,
+        glfw::Key::Escape => AurenKey::Escape,
+        glfw::Key::Enter => AurenKey::Enter,
+        glfw::Key::Tab => AurenKey::Tab,
+        glfw::Key::Backspace => AurenKey::Backspace,
+        glfw::Key::Insert => AurenKey::Insert,
+        glfw::Key::Delete => AurenKey::Delete,
+        glfw::Key::Right => AurenKey::Right,
+        glfw::Key::Left => AurenKey::Left,
+        glfw::Key::Down => AurenKey::Down,
+        glfw::Key::Up => AurenKey::Up,
+        glfw::Key::PageUp => AurenKey::PageUp,
+        glfw::Key::PageDown => AurenKey::PageDown,
+        glfw::Key::Home => AurenKey::Home,
+        glfw::Key::End => AurenKey::End,
+        glfw::Key::CapsLock => AurenKey::CapsLock,
+        glfw::Key::ScrollLock => AurenKey::ScrollLock,
+        glfw::Key::NumLock => AurenKey::NumLock,
+        glfw::Key::PrintScreen => AurenKey::PrintScreen,
+        glfw::Key::Pause => AurenKey::Pause,
+        glfw::Key::F1 => AurenKey::F1,
+        glfw::Key::F2 => AurenKey::F2,
+        glfw::Key::F3 => AurenKey::F3,
+        glfw::Key::F4 => AurenKey::F4,
+        glfw::Key::F5 => AurenKey::F5,
+        glfw::Key::F6 => AurenKey::F6,
+        glfw::Key::F7 => AurenKey::F7,
+        glfw::Key::F8 => AurenKey::F8,
+        glfw::Key::F9 => AurenKey::F9,
+        glfw::Key::F10 => AurenKey::F10,
+        glfw::Key::F11 => AurenKey::F11,
+        glfw::Key::F12 => AurenKey::F12,
+        glfw::Key::F13 => AurenKey::F13,
+        glfw::Key::F14 => AurenKey::F14,
+        glfw::Key::F15 => AurenKey::F15,
+        glfw::Key::F16 => AurenKey::F16,
+        glfw::Key::F17 => AurenKey::F17,
+        glfw::Key::F18 => AurenKey::F18,
+        glfw::Key::F19 => AurenKey::F19,
+        glfw::Key::F20 => AurenKey::F20,
+        glfw::Key::F21 => AurenKey::F21,
+        glfw::Key::F22 => AurenKey::F22,
+        glfw::Key::F23 => AurenKey::F23,
+        glfw::Key::F24 => AurenKey::F24,
+        glfw::Key::F25 => AurenKey::F25,
+        glfw::Key::Kp0 => AurenKey::Kp0,
+        glfw::Key::Kp1 => AurenKey::Kp1,
+        glfw::Key::Kp2 => AurenKey::Kp2,
+        glfw::Key::Kp3 => AurenKey::Kp3,
+        glfw::Key::Kp4 => AurenKey::Kp4,
+        glfw::Key::Kp5 => AurenKey::Kp5,
+        glfw::Key::Kp6 => AurenKey::Kp6,
+        glfw::Key::Kp7 => AurenKey::Kp7,
+        glfw::Key::Kp8 => AurenKey::Kp8,
+        glfw::Key::Kp9 => AurenKey::Kp9,
+        glfw::Key::KpDecimal => AurenKey::KpDecimal,
+        glfw::Key::KpDivide => AurenKey::KpDivide,
+        glfw::Key::KpMultiply => AurenKey::KpMultiply,
+        glfw::Key::KpSubtract => AurenKey::KpSubtract,
+        glfw::Key::KpAdd => AurenKey::KpAdd,
+        glfw::Key::KpEnter => AurenKey::KpEnter,
+        glfw::Key::KpEqual => AurenKey::KpEqual,
+        glfw::Key::LeftShift => AurenKey::LeftShift,
+        glfw::Key::LeftControl => AurenKey::LeftControl,
+        glfw::Key::LeftAlt => AurenKey::LeftAlt,
+        glfw::Key::LeftSuper => AurenKey::LeftSuper,
+        glfw::Key::RightShift => AurenKey::RightShift,
+        glfw::Key::RightControl => AurenKey::RightControl,
+        glfw::Key::RightAlt => AurenKey::RightAlt,
+        glfw::Key::RightSuper => AurenKey::RightSuper,
+        glfw::Key::Menu => AurenKey::Menu,
+        glfw::Key::Unknown => AurenKey::Unknown,
+    }
+}
@@ -0,0 +1,141 @@
+use std::{cell::RefCell, rc::Rc};
+use crate::error::AurenError;
+use crate::interfaces::{AurenEvent, GamepadAxis, GamepadButton, GamepadState, HatState, JoystickState};
+use crate::logging::target;
+
+extern crate glfw;
+
+const JOYSTICK_SLOTS: i32 = 16;
+
+fn translate_gamepad_button(button: GamepadButton) -> glfw::GamepadButton {
+    match button {
+        GamepadButton::A => glfw::GamepadButton::ButtonA,
+        GamepadButton::B => glfw::GamepadButton::ButtonB,
+        GamepadButton::X => glfw::GamepadButton::ButtonX,
+        GamepadButton::Y => glfw::GamepadButton::ButtonY,
+        GamepadButton::LeftBumper => glfw::GamepadButton::ButtonLeftBumper,
+        GamepadButton::RightBumper => glfw::GamepadButton::ButtonRightBumper,
+        GamepadButton::Back => glfw::GamepadButton::ButtonBack,
+        GamepadButton::Start => glfw::GamepadButton::ButtonStart,
+        GamepadButton::Guide => glfw::GamepadButton::ButtonGuide,
+        GamepadButton::LeftThumb => glfw::GamepadButton::ButtonLeftThumb,
+        GamepadButton::RightThumb => glfw::GamepadButton::ButtonRightThumb,
+        GamepadButton::DpadUp => glfw::GamepadButton::ButtonDpadUp,
+        GamepadButton::DpadRight => glfw::GamepadButton::ButtonDpadRight,
+        GamepadButton::DpadDown => glfw::GamepadButton::ButtonDpadDown,
+        GamepadButton::DpadLeft => glfw::GamepadButton::ButtonDpadLeft,
+    }
+}
+
+fn translate_gamepad_axis(axis: GamepadAxis) -> glfw::GamepadAxis {
+    match axis {
+        GamepadAxis::LeftX => glfw::GamepadAxis::AxisLeftX,
+        GamepadAxis::LeftY => glfw::GamepadAxis::AxisLeftY,
+        GamepadAxis::RightX => glfw::GamepadAxis::AxisRightX,
+        GamepadAxis::RightY => glfw::GamepadAxis::AxisRightY,
+        GamepadAxis::LeftTrigger => glfw::GamepadAxis::AxisLeftTrigger,
+        GamepadAxis::RightTrigger => glfw::GamepadAxis::AxisRightTrigger,
+    }
+}
+
+fn translate_hat(hat: glfw::JoystickHats) -> HatState {
+    HatState {
+        up: hat.contains(glfw::JoystickHats::Up),
+        right: hat.contains(glfw::JoystickHats::Right),
+        down: hat.contains(glfw::JoystickHats::Down),
+        left: hat.contains(glfw::JoystickHats::Left),
+    }
+}
+
+fn gamepad_state(joystick: &glfw::Joystick) -> Option<GamepadState> {
+    let state = joystick.get_gamepad_state()?;
+    let mut gamepad = GamepadState {
+        name: joystick.get_gamepad_name().unwrap_or_default(),
+        ..Default::default()
+    };
+
+    for button in GamepadButton::ALL {
+        gamepad.buttons[button as usize] = state.get_button_state(translate_gamepad_button(button)) == glfw::Action::Press;
+    }
+
+    for axis in GamepadAxis::ALL {
+        let value = state.get_axis(translate_gamepad_axis(axis));
+        gamepad.axes[axis as usize] = match axis {
+            // GLFW reports released triggers as -1.0
+            GamepadAxis::LeftTrigger | GamepadAxis::RightTrigger => (value + 1.0) / 2.0,
+            _ => value,
+        };
+    }
+
+    Some(gamepad)
+}
+
+pub struct AurenJoystickManager {
+    glfw: glfw::Glfw,
+    // Filled by the GLFW joystick callback as (joystick index, connected)
+    joystick_changes: Rc<RefCell<Vec<(usize, bool)>>>,
+}
+
+impl AurenJoystickManager {
+    pub fn new(glfw: &mut glfw::Glfw) -> Self {
+        let joystick_changes = Rc::new(RefCell::new(Vec::new()));
+        let callback_changes = Rc::clone(&joystick_changes);
+        glfw.set_joystick_callback(move |id, event| {
+            callback_changes.borrow_mut().push((id as usize, event == glfw::JoystickEvent::Connected));
+        });
+
+        Self {
+            glfw: glfw.clone(),
+            joystick_changes,
+        }
+    }
+
+    fn joystick(&self, index: usize) -> Option<glfw::Joystick> {
+        let id = glfw::JoystickId::from_i32(index as i32)?;
+        Some(self.glfw.get_joystick(id))
+    }
+
+    // Turns the connections reported since the last call into events
+    pub fn take_events(&mut self, time: f64) -> Vec<AurenEvent> {
+        self.joystick_changes.borrow_mut()
+            .drain(..)
+            .map(|(joystick, connected)| {
+                if connected {
+                    let name = self.joystick(joystick)
+                        .and_then(|j| j.get_name())
+                        .unwrap_or_default();
+                    log::info!(target: target::WINDOW, "Joystick {} '{}' connected", joystick, name);
+                    AurenEvent::JoystickConnected { time, joystick, name }
+                } else {
+                    log::info!(target: target::WINDOW, "Joystick {} disconnected", joystick);
+                    AurenEvent::JoystickDisconnected { time, joystick }
+                }
+            })
+            .collect()
+    }
+
+    pub fn poll(&self) -> Vec<JoystickState> {
+        (0..JOYSTICK_SLOTS as usize)
+            .filter_map(|index| self.joystick(index))
+            .filter(|joystick| joystick.is_present())
+            .map(|joystick| JoystickState {
+                index: joystick.id as usize,
+                name: joystick.get_name().unwrap_or_default(),
+                guid: joystick.get_guid(),
+                axes: joystick.get_axes(),
+                buttons: joystick.get_buttons().into_iter().map(|b| b == glfw::Action::Press as i32).collect(),
+                hats: joystick.get_hats().into_iter().map(translate_hat).collect(),
+                gamepad: if joystick.is_gamepad() { gamepad_state(&joystick) } else { None },
+            })
+            .collect()
+    }
+
+    pub fn update_gamepad_mappings(&mut self, mappings: &str) -> Result<(), AurenError> {
+        if !self.glfw.update_gamepad_mappings(mappings) {
+            return Err(AurenError::InvalidGamepadMappings);
+        }
+
+        log::debug!(target: target::WINDOW, "Updated gamepad mappings");
+        Ok(())
+    }
+}
@@ -1,28 +1,44 @@
 // Mods
 
 mod window_manager;
+mod joystick_manager;
 mod device_manager;
+mod device_selection;
+mod queue_families;
 mod vulkan_setup;
 mod swapchain;
+mod events;
 
 // Uses
 
-use crate::interfaces::RHI;
+use crate::error::AurenError;
+use crate::interfaces::{AurenEvent, ClientApi, Cursor, CursorMode, JoystickState, MonitorInfo, RHI, WindowDescriptor, WindowId, WindowMode, WindowState};
 use glfw::Context;
+use ash::vk::{self, Handle};
+use std::collections::HashMap;
+use std::time::Duration;
 use window_manager::AurenWindowManager;
+use joystick_manager::AurenJoystickManager;
 use device_manager::AurenDeviceManager;
 use vulkan_setup::AurenVulkanSetup;
-use swapchain::AurenSwapchain;
+pub use vulkan_setup::{DebugErrorAction, VulkanDebugConfig};
+pub use device_selection::{DeviceCandidate, DevicePreference, DeviceReport, DeviceSelection, DEVICE_ENV_VAR};
+pub use queue_families::{DeviceQueues, QueueFamilies};
+use swapchain::{AurenSwapchain, PresentStatus, SwapChainSupportDetails, WindowSurface};
+use crate::logging::target;
 
 // Structures
 
 pub struct GLFWVulkanAgent {
     vulkan_setup: AurenVulkanSetup,
     window_handler: AurenWindowManager,
+    joystick_manager: AurenJoystickManager,
     device_manager: AurenDeviceManager,
-    swapchain: AurenSwapchain,
+    device_selection: DeviceSelection,
+    // Surface and swap chain of every window without an OpenGL context
+    swapchains: HashMap<WindowId, WindowSurface>,
 
-    primary_window_id: Option<usize>,
+    primary_window_id: Option<WindowId>,
     program_should_end: bool,
 }
 
@@ -30,22 +46,241 @@ pub struct GLFWVulkanAgent {
 // Implementations
 
 impl GLFWVulkanAgent {
-    pub fn new() -> Self {
+    pub fn new() -> Result<Self, AurenError> {
+        Self::with_debug(VulkanDebugConfig::default())
+    }
+
+    // Same as new, with explicit validation settings instead of the build defaults
+    pub fn with_debug(debug_config: VulkanDebugConfig) -> Result<Self, AurenError> {
+        Self::with_config(debug_config, DeviceSelection::default())
+    }
+
+    // Same as with_debug, with the requirements and preference for the GPU
+    pub fn with_config(debug_config: VulkanDebugConfig, device_selection: DeviceSelection) -> Result<Self, AurenError> {
         let program_should_end = false;
-        Self {
-            vulkan_setup: AurenVulkanSetup::new(),
-            window_handler: AurenWindowManager::new(),
-            device_manager: AurenDeviceManager::new_empty(),
-            swapchain: AurenSwapchain::new(),
+        let mut window_handler = AurenWindowManager::new()?;
+        let vulkan_setup = AurenVulkanSetup::new(debug_config, &Self::window_extensions(&window_handler)?)?;
+        let joystick_manager = AurenJoystickManager::new(&mut window_handler.glfw);
+        let device_manager = Self::create_device(&vulkan_setup, &window_handler, &device_selection)?;
+        vulkan_setup.check_validation_errors();
+        Ok(Self {
+            vulkan_setup,
+            window_handler,
+            joystick_manager,
+            device_manager,
+            device_selection,
+            swapchains: HashMap::new(),
 
             primary_window_id: None,
             program_should_end,
+        })
+    }
+
+    // Why each GPU was accepted or rejected, and which one is in use
+    pub fn device_report(&self) -> &DeviceReport {
+        self.device_manager.report()
+    }
+
+    // The instance extensions GLFW needs to create surfaces on this platform
+    fn window_extensions(window_handler: &AurenWindowManager) -> Result<Vec<String>, AurenError> {
+        if !window_handler.glfw.vulkan_supported() {
+            return Err(AurenError::MissingVulkanLoader("GLFW found no Vulkan support".to_string()));
+        }
+
+        window_handler.glfw
+            .get_required_instance_extensions()
+            .ok_or_else(|| AurenError::MissingVulkanLoader("GLFW can't create Vulkan surfaces".to_string()))
+    }
+
+    fn create_device(
+        vulkan_setup: &AurenVulkanSetup,
+        window_handler: &AurenWindowManager,
+        device_selection: &DeviceSelection,
+    ) -> Result<AurenDeviceManager, AurenError> {
+        let instance = vulkan_setup.instance.handle();
+        let present_support = |physical_device: vk::PhysicalDevice, queue_family: u32| {
+            window_handler.glfw.get_physical_device_presentation_support_raw(
+                instance.as_raw() as usize as _,
+                physical_device.as_raw() as usize as _,
+                queue_family,
+            )
+        };
+
+        AurenDeviceManager::new(&vulkan_setup.instance, device_selection, &present_support)
+    }
+
+    // Creates the surface of a window and its swap chain on the shared device
+    fn create_swapchain(&mut self, id: WindowId) -> Result<(), AurenError> {
+        let window = self.window_handler.window(id)?;
+        let instance = &self.vulkan_setup.instance;
+        let surface_loader = &self.vulkan_setup.surface_loader;
+        let physical_device = self.device_manager.get_physical_device();
+        let queue_families = self.device_manager.get_queue_families().ok_or(AurenError::NoSuitableGpu)?;
+
+        let mut raw_surface = std::ptr::null_mut();
+        let result = unsafe {
+            window.window.create_window_surface(instance.handle().as_raw() as usize as _, std::ptr::null(), &mut raw_surface)
+        };
+        vk::Result::from_raw(result as i32)
+            .result()
+            .map_err(|e| AurenError::vulkan("create window surface", e))?;
+        let surface = vk::SurfaceKHR::from_raw(raw_surface as usize as u64);
+
+        // The present family was picked for the platform, not for this particular surface
+        let supported = unsafe {
+            surface_loader.get_physical_device_surface_support(physical_device, queue_families.present, surface)
+        };
+        if supported != Ok(true) {
+            unsafe { surface_loader.destroy_surface(surface, None) };
+            return Err(match supported {
+                Err(e) => AurenError::vulkan("query surface support", e),
+                Ok(_) => AurenError::SwapchainUnsupported,
+            });
+        }
+
+        self.swapchains.insert(id, WindowSurface::new(surface));
+
+        // A window created minimized gets its swap chain once it has a size
+        if let Err(e) = self.rebuild_swapchain(id) {
+            self.destroy_swapchain(id);
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    // Replaces the swap chain of a window, handing the old one to Vulkan for reuse.
+    // Does nothing while the surface has no area, the window stays outdated until then.
+    fn rebuild_swapchain(&mut self, id: WindowId) -> Result<(), AurenError> {
+        let window = self.window_handler.window(id)?;
+        let Some(window_surface) = self.swapchains.get_mut(&id) else { return Ok(()) };
+        let swapchain_loader = self.device_manager.get_swapchain_loader().ok_or(AurenError::SwapchainUnsupported)?;
+        let device = self.device_manager.get_logical_device();
+        let physical_device = self.device_manager.get_physical_device();
+        let queue_families = self.device_manager.get_queue_families().ok_or(AurenError::NoSuitableGpu)?;
+
+        let framebuffer_size = (window.framebuffer_width, window.framebuffer_height);
+        if framebuffer_size.0 == 0 || framebuffer_size.1 == 0 {
+            return Ok(());
+        }
+
+        let support_details = SwapChainSupportDetails::new(physical_device, &self.vulkan_setup.surface_loader, window_surface.surface)?;
+        let current_extent = support_details.capabilities.current_extent;
+        if current_extent.width == 0 || current_extent.height == 0 {
+            return Ok(());
+        }
+
+        let old_swapchain = window_surface.swapchain.take();
+        if old_swapchain.is_some() {
+            // The old image views and semaphores may still be in use by the last frames
+            unsafe { device.device_wait_idle() }
+                .map_err(|e| AurenError::vulkan("wait for the device", e))?;
+        }
+
+        let created = AurenSwapchain::new(
+            framebuffer_size,
+            swapchain_loader,
+            &support_details,
+            window_surface.surface,
+            device,
+            &queue_families,
+            old_swapchain.as_ref().map_or(vk::SwapchainKHR::null(), |old| old.swapchain_khr),
+        );
+
+        // Passing the old swap chain retires it, even when creating the new one failed
+        if let Some(mut old_swapchain) = old_swapchain {
+            old_swapchain.destroy(device, swapchain_loader);
+        }
+
+        let swapchain = created?;
+        log::debug!(
+            target: target::SWAPCHAIN,
+            "Swap chain of window {} is {}x{}",
+            id, swapchain.swapchain_extent.width, swapchain.swapchain_extent.height,
+        );
+        window_surface.swapchain = Some(swapchain);
+        window_surface.outdated = false;
+        Ok(())
+    }
+
+    // Tears down the swap chain and surface of a window, before the window itself is destroyed
+    fn destroy_swapchain(&mut self, id: WindowId) {
+        let Some(mut window_surface) = self.swapchains.remove(&id) else { return };
+        let device = self.device_manager.get_logical_device();
+
+        if let Some(mut swapchain) = window_surface.swapchain.take() {
+            // The presentation engine may still hold images and semaphores of this swap chain
+            unsafe {
+                let _ = device.device_wait_idle();
+            }
+
+            if let Some(swapchain_loader) = self.device_manager.get_swapchain_loader() {
+                swapchain.destroy(device, swapchain_loader);
+            }
+        }
+        unsafe { self.vulkan_setup.surface_loader.destroy_surface(window_surface.surface, None) };
+    }
+
+    fn destroy_all_swapchains(&mut self) {
+        let ids: Vec<WindowId> = self.swapchains.keys().copied().collect();
+        for id in ids {
+            self.destroy_swapchain(id);
+        }
+    }
+
+    // Swap chains of resized windows are rebuilt before their next present
+    fn mark_resized_swapchains(&mut self) {
+        for event in &self.window_handler.frame_events {
+            if let AurenEvent::FramebufferResized { window, .. } = event
+                && let Some(window_surface) = self.swapchains.get_mut(window)
+            {
+                window_surface.outdated = true;
+            }
+        }
+    }
+
+    fn present(&mut self) {
+        let ids: Vec<WindowId> = self.swapchains.keys().copied().collect();
+
+        for id in ids {
+            // Minimized windows have no framebuffer, rendering pauses until they are restored
+            let Ok(window) = self.window_handler.window(id) else { continue };
+            if window.framebuffer_width == 0 || window.framebuffer_height == 0 {
+                continue;
+            }
+
+            if self.swapchains.get(&id).is_some_and(|s| s.outdated)
+                && let Err(e) = self.rebuild_swapchain(id)
+            {
+                log::error!(target: target::SWAPCHAIN, "Failed to recreate the swap chain of window {}: {}", id, e);
+                continue;
+            }
+
+            let Some(swapchain_loader) = self.device_manager.get_swapchain_loader() else { return };
+            let device = self.device_manager.get_logical_device();
+            let queues = self.device_manager.get_queues();
+            let Some(window_surface) = self.swapchains.get_mut(&id) else { continue };
+            let Some(swapchain) = window_surface.swapchain.as_mut() else { continue };
+
+            match swapchain.present(device, swapchain_loader, &queues) {
+                Ok(PresentStatus::Presented) => {}
+                Ok(status) => {
+                    log::debug!(target: target::SWAPCHAIN, "Swap chain of window {} is {:?}, recreating it", id, status);
+                    window_surface.outdated = true;
+                }
+                Err(e) => {
+                    log::error!(target: target::SWAPCHAIN, "Failed to present window {}: {}", id, e);
+                }
+            }
         }
     }
 
     fn swap_buffers(&mut self) {
+        // Windows without an OpenGL context have no buffers to swap
         for window in &mut self.window_handler.windows {
-            window.window.swap_buffers();
+            if window.client_api != ClientApi::NoApi {
+                window.window.swap_buffers();
+            }
         }
     }
 
@@ -53,15 +288,6 @@ impl GLFWVulkanAgent {
         self.window_handler.windows.len()
     }
 
-    #[allow(dead_code)]
-    fn get_window_title(&self, index: usize) -> Option<&str> {
-        if index < self.window_handler.windows.len() {
-            Some(&self.window_handler.windows[index].title)
-        } else {
-            None
-        }
-    }
-
     #[allow(dead_code)]
     fn make_current(&mut self, index: usize) {
         if index < self.window_handler.windows.len() {
@@ -72,27 +298,60 @@ impl GLFWVulkanAgent {
     }
 
     fn cleanup_closed_windows(&mut self) {
-        // This looks at every window and only keeps it if should_close is FALSE
-        self.window_handler.windows.retain(|w| {
-            !w.window.should_close()
-        });
+        for id in self.window_handler.closed_window_ids() {
+            self.destroy_swapchain(id);
+        }
+        self.window_handler.cleanup_closed_windows();
+    }
+}
+
+impl Drop for GLFWVulkanAgent {
+    fn drop(&mut self) {
+        // Swap chains and surfaces first, then the device, the instance goes with vulkan_setup
+        self.destroy_all_swapchains();
+        self.device_manager = AurenDeviceManager::new_empty();
     }
 }
 
 impl RHI for GLFWVulkanAgent {
-    fn new(&mut self) {
-        self.vulkan_setup = AurenVulkanSetup::new();
-        self.device_manager = AurenDeviceManager::new(&self.vulkan_setup.instance);
-        self.window_handler = AurenWindowManager::new();
+    fn reinitialize(&mut self) -> Result<(), AurenError> {
+        // Everything new is created first, so a failure leaves the agent as it was
+        let mut window_handler = AurenWindowManager::new()?;
+        let vulkan_setup = AurenVulkanSetup::new(self.vulkan_setup.debug_config, &Self::window_extensions(&window_handler)?)?;
+        let device_manager = Self::create_device(&vulkan_setup, &window_handler, &self.device_selection)?;
+        vulkan_setup.check_validation_errors();
+
+        // Drop the old swap chains and device before the instance they were created from
+        self.destroy_all_swapchains();
+        self.device_manager = device_manager;
+        self.vulkan_setup = vulkan_setup;
+        self.joystick_manager = AurenJoystickManager::new(&mut window_handler.glfw);
+        self.window_handler = window_handler;
         self.primary_window_id = None;
+        Ok(())
     }
 
-    fn assign_master(&mut self, id: usize) {
+    fn assign_master(&mut self, id: WindowId) -> Result<(), AurenError> {
+        self.window_handler.ids.validate(id)?;
         self.primary_window_id = Some(id);
+        Ok(())
     }
 
-    fn create_window(&mut self, title: &str, width: u32, height: u32, id: Option<usize>) -> Result<usize, String> {
-        self.window_handler.create_window(title, width, height, id)
+    fn validate_window(&self, id: WindowId) -> Result<(), AurenError> {
+        self.window_handler.ids.validate(id)
+    }
+
+    fn create_window(&mut self, descriptor: &WindowDescriptor, id: Option<usize>) -> Result<WindowId, AurenError> {
+        let id = self.window_handler.create_window(descriptor, id)?;
+
+        // A window with an OpenGL context can't have a Vulkan surface
+        if descriptor.client_api == ClientApi::NoApi && let Err(e) = self.create_swapchain(id) {
+            let _ = self.window_handler.destroy_window(id);
+            return Err(e);
+        }
+
+        self.vulkan_setup.check_validation_errors();
+        Ok(id)
     }
 
     fn start_frame(&mut self) {
@@ -111,18 +370,166 @@ impl RHI for GLFWVulkanAgent {
             }
 
         self.window_handler.update();
+        self.mark_resized_swapchains();
+
+        let time = self.window_handler.glfw.get_time();
+        let joystick_events = self.joystick_manager.take_events(time);
+        self.window_handler.frame_events.extend(joystick_events);
     }
 
     fn end_frame(&mut self) {
         if self.program_should_end { return; }
+        self.present();
         self.swap_buffers();
+        self.vulkan_setup.check_validation_errors();
     }
 
-    fn destroy_window(&mut self, id : usize) {
-        self.window_handler.destroy_window(id);
+    fn destroy_window(&mut self, id: WindowId) -> Result<(), AurenError> {
+        self.window_handler.ids.validate(id)?;
+        self.destroy_swapchain(id);
+        self.vulkan_setup.check_validation_errors();
+        self.window_handler.destroy_window(id)
     }
 
     fn should_close(&self) -> bool {
-        return self.program_should_end;
+        self.program_should_end
+    }
+
+    fn window_title(&self, id: WindowId) -> Result<String, AurenError> {
+        Ok(self.window_handler.window(id)?.title.clone())
+    }
+
+    fn set_window_title(&mut self, id: WindowId, title: &str) -> Result<(), AurenError> {
+        let window = self.window_handler.window_mut(id)?;
+        window.window.set_title(title);
+        window.title = title.to_string();
+        Ok(())
+    }
+
+    fn window_size(&self, id: WindowId) -> Result<(u32, u32), AurenError> {
+        let window = self.window_handler.window(id)?;
+        Ok((window.width, window.height))
+    }
+
+    fn framebuffer_size(&self, id: WindowId) -> Result<(u32, u32), AurenError> {
+        let window = self.window_handler.window(id)?;
+        Ok((window.framebuffer_width, window.framebuffer_height))
+    }
+
+    fn content_scale(&self, id: WindowId) -> Result<(f32, f32), AurenError> {
+        Ok(self.window_handler.window(id)?.content_scale)
+    }
+
+    fn set_window_size(&mut self, id: WindowId, width: u32, height: u32) -> Result<(), AurenError> {
+        let width = width.min(i32::MAX as u32) as i32;
+        let height = height.min(i32::MAX as u32) as i32;
+        self.window_handler.window_mut(id)?.window.set_size(width, height);
+        Ok(())
+    }
+
+    fn window_position(&self, id: WindowId) -> Result<(i32, i32), AurenError> {
+        Ok(self.window_handler.window(id)?.window.get_pos())
+    }
+
+    fn set_window_position(&mut self, id: WindowId, x: i32, y: i32) -> Result<(), AurenError> {
+        self.window_handler.window_mut(id)?.window.set_pos(x, y);
+        Ok(())
+    }
+
+    fn window_state(&self, id: WindowId) -> Result<WindowState, AurenError> {
+        let window = &self.window_handler.window(id)?.window;
+        Ok(WindowState {
+            minimized: window.is_iconified(),
+            maximized: window.is_maximized(),
+            visible: window.is_visible(),
+            focused: window.is_focused(),
+        })
+    }
+
+    fn minimize_window(&mut self, id: WindowId) -> Result<(), AurenError> {
+        self.window_handler.window_mut(id)?.window.iconify();
+        Ok(())
+    }
+
+    fn maximize_window(&mut self, id: WindowId) -> Result<(), AurenError> {
+        self.window_handler.window_mut(id)?.window.maximize();
+        Ok(())
+    }
+
+    fn restore_window(&mut self, id: WindowId) -> Result<(), AurenError> {
+        self.window_handler.window_mut(id)?.window.restore();
+        Ok(())
+    }
+
+    fn show_window(&mut self, id: WindowId) -> Result<(), AurenError> {
+        self.window_handler.window_mut(id)?.window.show();
+        Ok(())
+    }
+
+    fn hide_window(&mut self, id: WindowId) -> Result<(), AurenError> {
+        self.window_handler.window_mut(id)?.window.hide();
+        Ok(())
+    }
+
+    fn focus_window(&mut self, id: WindowId) -> Result<(), AurenError> {
+        self.window_handler.window_mut(id)?.window.focus();
+        Ok(())
+    }
+
+    fn request_window_attention(&mut self, id: WindowId) -> Result<(), AurenError> {
+        self.window_handler.window_mut(id)?.window.request_attention();
+        Ok(())
+    }
+
+    fn cursor_mode(&self, id: WindowId) -> Result<CursorMode, AurenError> {
+        self.window_handler.cursor_mode(id)
+    }
+
+    fn set_cursor_mode(&mut self, id: WindowId, mode: CursorMode) -> Result<(), AurenError> {
+        self.window_handler.set_cursor_mode(id, mode)
+    }
+
+    fn set_cursor(&mut self, id: WindowId, cursor: &Cursor) -> Result<(), AurenError> {
+        self.window_handler.set_cursor(id, cursor)
+    }
+
+    // GLFW reaches the clipboard through a window, any of them will do
+    fn get_clipboard_text(&self) -> Option<String> {
+        self.window_handler.windows.first()?.window.get_clipboard_string()
+    }
+
+    fn set_clipboard_text(&mut self, text: &str) -> Result<(), AurenError> {
+        let window = self.window_handler.windows.first_mut().ok_or(AurenError::ClipboardUnavailable)?;
+        window.window.set_clipboard_string(text);
+        Ok(())
+    }
+
+    fn monitors(&mut self) -> Vec<MonitorInfo> {
+        self.window_handler.monitors()
+    }
+
+    fn window_mode(&self, id: WindowId) -> Result<WindowMode, AurenError> {
+        Ok(self.window_handler.window(id)?.mode)
+    }
+
+    fn set_window_mode(&mut self, id: WindowId, mode: WindowMode) -> Result<(), AurenError> {
+        self.window_handler.set_window_mode(id, mode)
+    }
+
+    fn poll_joysticks(&mut self) -> Vec<JoystickState> {
+        self.joystick_manager.poll()
+    }
+
+    fn update_gamepad_mappings(&mut self, mappings: &str) -> Result<(), AurenError> {
+        self.joystick_manager.update_gamepad_mappings(mappings)
+    }
+
+    fn wait_events(&mut self, timeout: Option<Duration>) {
+        if self.program_should_end { return; }
+        self.window_handler.wait_events(timeout);
+    }
+
+    fn drain_events(&mut self) -> Vec<AurenEvent> {
+        std::mem::take(&mut self.window_handler.frame_events)
     }
 }
\ No newline at end of file
@@ -0,0 +1,233 @@
+use ash::vk;
+
+// Queue family index of every role. Roles share a family when the hardware offers no dedicated one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct QueueFamilies {
+    pub graphics: u32,
+    // Equal to graphics whenever the graphics family can present
+    pub present: u32,
+    // A compute-only family (async compute) if the device has one, graphics otherwise
+    pub compute: u32,
+    // A transfer-only family (DMA engine) if the device has one
+    pub transfer: u32,
+}
+
+impl QueueFamilies {
+    // None when the device has no graphics family, or nothing can present while presentation is required
+    pub(crate) fn find(
+        families: &[vk::QueueFamilyProperties],
+        can_present: impl Fn(u32) -> bool,
+        presentation: bool,
+    ) -> Option<Self> {
+        let with = |required: vk::QueueFlags, excluded: vk::QueueFlags| {
+            families.iter()
+                .enumerate()
+                .filter(|(_, family)| family.queue_count > 0)
+                .filter(|(_, family)| family.queue_flags.contains(required) && !family.queue_flags.intersects(excluded))
+                .map(|(index, _)| index as u32)
+                .collect::<Vec<u32>>()
+        };
+
+        let graphics_families = with(vk::QueueFlags::GRAPHICS, vk::QueueFlags::empty());
+
+        let (graphics, present) = if presentation {
+            match graphics_families.iter().copied().find(|&index| can_present(index)) {
+                Some(index) => (index, index),
+                None => {
+                    let graphics = *graphics_families.first()?;
+                    let present = (0..families.len() as u32)
+                        .filter(|&index| families[index as usize].queue_count > 0)
+                        .find(|&index| can_present(index))?;
+                    (graphics, present)
+                }
+            }
+        } else {
+            let graphics = *graphics_families.first()?;
+            (graphics, graphics)
+        };
+
+        let compute = with(vk::QueueFlags::COMPUTE, vk::QueueFlags::GRAPHICS)
+            .first()
+            .copied()
+            .unwrap_or(graphics);
+
+        // Graphics and compute families can always transfer, even when they don't report it
+        let transfer = with(vk::QueueFlags::TRANSFER, vk::QueueFlags::GRAPHICS | vk::QueueFlags::COMPUTE)
+            .first()
+            .or(with(vk::QueueFlags::TRANSFER, vk::QueueFlags::GRAPHICS).first())
+            .copied()
+            .unwrap_or(compute);
+
+        Some(Self {
+            graphics,
+            present,
+            compute,
+            transfer,
+        })
+    }
+
+    // Every family used by at least one role, without duplicates
+    pub fn unique(&self) -> Vec<u32> {
+        let mut families = vec![self.graphics, self.present, self.compute, self.transfer];
+        families.sort_unstable();
+        families.dedup();
+        families
+    }
+
+    pub fn has_dedicated_compute(&self) -> bool {
+        self.compute != self.graphics
+    }
+
+    pub fn has_dedicated_transfer(&self) -> bool {
+        self.transfer != self.graphics && self.transfer != self.compute
+    }
+
+    // Images shared between graphics and present then need CONCURRENT sharing or ownership transfers
+    pub fn present_is_separate(&self) -> bool {
+        self.present != self.graphics
+    }
+}
+
+// The queues created on the logical device. Roles on the same family get their own queue
+// while the family has enough of them, and share the last one otherwise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DeviceQueues {
+    pub graphics: vk::Queue,
+    pub present: vk::Queue,
+    pub compute: vk::Queue,
+    pub transfer: vk::Queue,
+}
+
+// (family, queue index) of every role, plus how many queues to create per family
+pub(crate) struct QueueLayout {
+    pub counts: Vec<(u32, u32)>,
+    pub graphics: (u32, u32),
+    pub present: (u32, u32),
+    pub compute: (u32, u32),
+    pub transfer: (u32, u32),
+}
+
+impl QueueLayout {
+    pub fn new(families: &QueueFamilies, properties: &[vk::QueueFamilyProperties]) -> Self {
+        let mut counts: Vec<(u32, u32)> = Vec::new();
+        let mut take = |family: u32| {
+            let available = properties[family as usize].queue_count.max(1);
+            let position = match counts.iter().position(|&(f, _)| f == family) {
+                Some(position) => position,
+                None => {
+                    counts.push((family, 0));
+                    counts.len() - 1
+                }
+            };
+            let used = &mut counts[position].1;
+
+            if *used < available {
+                *used += 1;
+            }
+            (family, *used - 1)
+        };
+
+        let graphics = take(families.graphics);
+        let compute = take(families.compute);
+        let transfer = take(families.transfer);
+        // Presenting from the graphics queue avoids a queue switch every frame
+        let present = if families.present == families.graphics {
+            graphics
+        } else {
+            take(families.present)
+        };
+
+        Self {
+            counts,
+            graphics,
+            present,
+            compute,
+            transfer,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GRAPHICS: vk::QueueFlags = vk::QueueFlags::from_raw(
+        vk::QueueFlags::GRAPHICS.as_raw() | vk::QueueFlags::COMPUTE.as_raw() | vk::QueueFlags::TRANSFER.as_raw(),
+    );
+    const COMPUTE: vk::QueueFlags = vk::QueueFlags::from_raw(vk::QueueFlags::COMPUTE.as_raw() | vk::QueueFlags::TRANSFER.as_raw());
+
+    fn family(queue_flags: vk::QueueFlags, queue_count: u32) -> vk::QueueFamilyProperties {
+        vk::QueueFamilyProperties { queue_flags, queue_count, ..Default::default() }
+    }
+
+    #[test]
+    fn single_family_takes_every_role() {
+        let families = [family(GRAPHICS, 1)];
+        let found = QueueFamilies::find(&families, |_| true, true).unwrap();
+
+        assert_eq!(found, QueueFamilies { graphics: 0, present: 0, compute: 0, transfer: 0 });
+        assert_eq!(found.unique(), vec![0]);
+        assert!(!found.has_dedicated_compute());
+        assert!(!found.has_dedicated_transfer());
+        assert!(!found.present_is_separate());
+    }
+
+    #[test]
+    fn prefers_dedicated_compute_and_transfer_families() {
+        let families = [family(vk::QueueFlags::TRANSFER, 2), family(GRAPHICS, 16), family(COMPUTE, 8)];
+        let found = QueueFamilies::find(&families, |_| true, true).unwrap();
+
+        assert_eq!(found, QueueFamilies { graphics: 1, present: 1, compute: 2, transfer: 0 });
+        assert!(found.has_dedicated_compute());
+        assert!(found.has_dedicated_transfer());
+        assert_eq!(found.unique(), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn presents_from_graphics_whenever_it_can() {
+        let families = [family(GRAPHICS, 1), family(GRAPHICS, 1)];
+        let found = QueueFamilies::find(&families, |index| index == 1, true).unwrap();
+
+        assert_eq!((found.graphics, found.present), (1, 1));
+    }
+
+    #[test]
+    fn falls_back_to_a_separate_present_family() {
+        let families = [family(GRAPHICS, 1), family(vk::QueueFlags::TRANSFER, 1)];
+        let found = QueueFamilies::find(&families, |index| index == 1, true).unwrap();
+
+        assert_eq!((found.graphics, found.present), (0, 1));
+        assert!(found.present_is_separate());
+    }
+
+    #[test]
+    fn requires_graphics_and_presentation() {
+        assert_eq!(QueueFamilies::find(&[family(COMPUTE, 4)], |_| true, true), None);
+        assert_eq!(QueueFamilies::find(&[family(GRAPHICS, 0)], |_| true, true), None);
+        assert_eq!(QueueFamilies::find(&[family(GRAPHICS, 1)], |_| false, true), None);
+        assert!(QueueFamilies::find(&[family(GRAPHICS, 1)], |_| false, false).is_some());
+    }
+
+    #[test]
+    fn layout_gives_roles_their_own_queues_while_there_are_enough() {
+        let properties = [family(GRAPHICS, 4)];
+        let families = QueueFamilies { graphics: 0, present: 0, compute: 0, transfer: 0 };
+        let layout = QueueLayout::new(&families, &properties);
+
+        assert_eq!(layout.counts, vec![(0, 3)]);
+        assert_eq!((layout.graphics, layout.compute, layout.transfer), ((0, 0), (0, 1), (0, 2)));
+        // Present shares the graphics queue
+        assert_eq!(layout.present, (0, 0));
+    }
+
+    #[test]
+    fn layout_shares_the_last_queue_when_the_family_runs_out() {
+        let properties = [family(GRAPHICS, 1), family(vk::QueueFlags::TRANSFER, 1)];
+        let families = QueueFamilies { graphics: 0, present: 1, compute: 0, transfer: 0 };
+        let layout = QueueLayout::new(&families, &properties);
+
+        assert_eq!(layout.counts, vec![(0, 1), (1, 1)]);
+        assert_eq!((layout.graphics, layout.compute, layout.transfer), ((0, 0), (0, 0), (0, 0)));
+        assert_eq!(layout.present, (1, 0));
+    }
+}
@@ -1,6 +1,20 @@
-use ash::{Instance, khr, vk};
-use glfw::PWindow;
-use colored::*;
+use ash::{khr, vk};
+use crate::error::AurenError;
+use crate::logging::target;
+use super::queue_families::{DeviceQueues, QueueFamilies};
+
+// Frames the CPU may record ahead of the GPU
+const FRAMES_IN_FLIGHT: usize = 2;
+
+// Outcome of presenting one frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PresentStatus {
+    Presented,
+    // Presented, but the swap chain no longer matches the surface exactly
+    Suboptimal,
+    // Nothing was presented, the swap chain has to be recreated first
+    OutOfDate,
+}
 
 pub struct SwapChainSupportDetails {
     pub capabilities: vk::SurfaceCapabilitiesKHR,
@@ -13,25 +27,25 @@ impl SwapChainSupportDetails {
         physical_device: vk::PhysicalDevice,
         surface_loader: &khr::surface::Instance,
         surface: vk::SurfaceKHR,
-    ) -> Self {
+    ) -> Result<Self, AurenError> {
         unsafe {
             let capabilities = surface_loader
                 .get_physical_device_surface_capabilities(physical_device, surface)
-                .expect("Failed to query surface capabilities");
+                .map_err(|e| AurenError::vulkan("query surface capabilities", e))?;
 
             let formats = surface_loader
                 .get_physical_device_surface_formats(physical_device, surface)
-                .expect("Failed to query surface formats");
+                .map_err(|e| AurenError::vulkan("query surface formats", e))?;
 
             let present_modes = surface_loader
                 .get_physical_device_surface_present_modes(physical_device, surface)
-                .expect("Failed to query surface present modes");
+                .map_err(|e| AurenError::vulkan("query surface present modes", e))?;
 
-            Self {
+            Ok(Self {
                 capabilities,
                 formats,
                 present_modes,
-            }
+            })
         }
     }
 
@@ -40,66 +54,69 @@ impl SwapChainSupportDetails {
     }
 }
 
+// The Vulkan surface of a window and the swap chain presenting to it
+pub struct WindowSurface {
+    pub surface: vk::SurfaceKHR,
+    // None until the window has a size, or after recreating it failed
+    pub swapchain: Option<AurenSwapchain>,
+    // Rebuild the swap chain before the next present
+    pub outdated: bool,
+}
+
+impl WindowSurface {
+    pub fn new(surface: vk::SurfaceKHR) -> Self {
+        Self {
+            surface,
+            swapchain: None,
+            outdated: true,
+        }
+    }
+}
+
 pub struct AurenSwapchain {
-    pub surface_khr: vk::SurfaceKHR,
-    pub render_pass: vk::RenderPass,
-    
     pub swapchain_khr: vk::SwapchainKHR,
     pub swapchain_images: Vec<vk::Image>,
     pub swapchain_image_views: Vec<vk::ImageView>,
-    pub swapchain_image_format: vk::Format,
     pub swapchain_extent: vk::Extent2D,
-    pub swapchain_framebuffer: Vec<vk::Framebuffer>,
-    pub swapchain_frame_buffer: Vec<vk::Framebuffer>,
 
     pub command_pool: vk::CommandPool,
     pub command_buffers: Vec<vk::CommandBuffer>,
-    pub index_buffer: vk::Buffer,
-    pub support_details: SwapChainSupportDetails,
 
-    pub vsync_enabled: bool,
+    // Signalled when an acquired image may be written, one per frame in flight
+    pub image_available: Vec<vk::Semaphore>,
+    // Signalled when an image is ready to present, one per swap chain image
+    pub render_finished: Vec<vk::Semaphore>,
+    pub in_flight: Vec<vk::Fence>,
+    pub current_frame: usize,
+    // Color the images are cleared to before presenting
+    pub clear_color: [f32; 4],
+    // The surface allows TRANSFER_DST images, needed for the clear
+    pub can_clear: bool,
 }
 
 fn log_info(msg: &str) {
-    println!("{}{}->{} {} {}", 
-        "Auren".bright_cyan().bold(), 
-        "Fox".bright_red(), 
-        "Vulkan".on_red(), 
-        "[info]".bright_cyan(),
-        msg
-    );
+    log::info!(target: target::SWAPCHAIN, "{}", msg);
 }
 
-fn log_err(msg: &str){
-    panic!("{}{}->{} {} {}", 
-        "Auren".bright_cyan().bold(), 
-        "Fox".bright_red(), 
-        "Vulkan".on_red(), 
-        "[info]".bright_cyan(),
-        msg
-    );
+fn log_err(err: AurenError) -> AurenError {
+    log::error!(target: target::SWAPCHAIN, "{}", err);
+    err
 }
 
 impl AurenSwapchain {
-    fn choose_swap_surface_format(details: &SwapChainSupportDetails) -> vk::SurfaceFormatKHR {
+    fn choose_swap_surface_format(details: &SwapChainSupportDetails) -> Option<vk::SurfaceFormatKHR> {
         details.formats
             .iter()
             .find(|f| f.format == vk::Format::B8G8R8A8_SRGB && 
                     f.color_space == vk::ColorSpaceKHR::SRGB_NONLINEAR)
-            .cloned() 
-            .unwrap_or_else(|| {
-                details.formats.get(0)
-                    .cloned()
-                    .expect("Swap chain formats list is unexpectedly empty!")
-            })
+            .or_else(|| details.formats.first())
+            .cloned()
     }
 
     fn choose_swap_present_mode(vsync_enabled: bool, details: &SwapChainSupportDetails) -> vk::PresentModeKHR {
-        if !vsync_enabled {
-            if details.present_modes.contains(&vk::PresentModeKHR::IMMEDIATE) {
-                log_info("VSync disabled: IMMEDIATE mode");
-                return vk::PresentModeKHR::IMMEDIATE;
-            }
+        if !vsync_enabled && details.present_modes.contains(&vk::PresentModeKHR::IMMEDIATE) {
+            log_info("VSync disabled: IMMEDIATE mode");
+            return vk::PresentModeKHR::IMMEDIATE;
         }
 
         if details.present_modes.contains(&vk::PresentModeKHR::MAILBOX) {
@@ -111,31 +128,38 @@ impl AurenSwapchain {
         vk::PresentModeKHR::FIFO
     }
 
-    fn choose_swap_extent(window: &PWindow, details: &SwapChainSupportDetails) -> vk::Extent2D {
+    // Takes the framebuffer size in pixels, never the window size in screen coordinates,
+    // which is smaller on HiDPI displays
+    fn choose_swap_extent(framebuffer_size: (u32, u32), details: &SwapChainSupportDetails) -> vk::Extent2D {
         if details.capabilities.current_extent.width != u32::MAX {
             return details.capabilities.current_extent;
         }
 
-        let (width, height) = window.get_framebuffer_size();
+        let (width, height) = framebuffer_size;
 
         vk::Extent2D {
-            width: (width as u32).clamp(
+            width: width.clamp(
                 details.capabilities.min_image_extent.width,
                 details.capabilities.max_image_extent.width,
             ),
-            height: (height as u32).clamp(
+            height: height.clamp(
                 details.capabilities.min_image_extent.height,
                 details.capabilities.max_image_extent.height,
             ),
         }
     }
 
-    pub fn new(window: &PWindow, instance: Instance, support_details: SwapChainSupportDetails, surface_khr: vk::SurfaceKHR, device: &ash::Device) -> Self {
+    pub fn new(framebuffer_size: (u32, u32), swapchain_loader: &khr::swapchain::Device, support_details: &SwapChainSupportDetails, surface_khr: vk::SurfaceKHR, device: &ash::Device, queue_families: &QueueFamilies, old_swapchain: vk::SwapchainKHR) -> Result<Self, AurenError> {
         let vsync_enabled = true;
 
-        let surface_format = Self::choose_swap_surface_format(&support_details);
-        let present_mode = Self::choose_swap_present_mode(vsync_enabled, &support_details);
-        let extent = Self::choose_swap_extent(&window, &support_details);
+        if !support_details.is_complete() {
+            return Err(log_err(AurenError::SwapchainUnsupported));
+        }
+
+        let surface_format = Self::choose_swap_surface_format(support_details)
+            .ok_or_else(|| log_err(AurenError::SwapchainUnsupported))?;
+        let present_mode = Self::choose_swap_present_mode(vsync_enabled, support_details);
+        let extent = Self::choose_swap_extent(framebuffer_size, support_details);
 
         let swapchain_image_format = surface_format.format;
         let swapchain_extent = extent;
@@ -145,7 +169,20 @@ impl AurenSwapchain {
             image_count = support_details.capabilities.max_image_count;
         }
 
-        let swapchain_loader = ash::khr::swapchain::Device::new(&instance, device);
+        let can_clear = support_details.capabilities.supported_usage_flags.contains(vk::ImageUsageFlags::TRANSFER_DST);
+        let mut image_usage = vk::ImageUsageFlags::COLOR_ATTACHMENT;
+        if can_clear {
+            image_usage |= vk::ImageUsageFlags::TRANSFER_DST;
+        }
+
+        // Images are rendered on the graphics queue and presented on the present queue. When
+        // those are different families, CONCURRENT sharing saves the ownership transfers.
+        let shared_families = [queue_families.graphics, queue_families.present];
+        let (sharing_mode, family_indices): (_, &[u32]) = if queue_families.present_is_separate() {
+            (vk::SharingMode::CONCURRENT, &shared_families)
+        } else {
+            (vk::SharingMode::EXCLUSIVE, &[])
+        };
 
         // Create Info //
 
@@ -156,13 +193,15 @@ impl AurenSwapchain {
             .image_color_space(surface_format.color_space)
             .image_extent(extent)
             .image_array_layers(1)
-            .image_usage(vk::ImageUsageFlags::COLOR_ATTACHMENT)
-            .image_sharing_mode(vk::SharingMode::EXCLUSIVE) // Use EXCLUSIVE if Graphics == Present
+            .image_usage(image_usage)
+            .image_sharing_mode(sharing_mode)
+            .queue_family_indices(family_indices)
             .pre_transform(support_details.capabilities.current_transform)
             .composite_alpha(vk::CompositeAlphaFlagsKHR::OPAQUE)
             .present_mode(present_mode)
             .clipped(true)
-            .old_swapchain(vk::SwapchainKHR::null());
+            // Lets the driver reuse the resources of the swap chain being replaced
+            .old_swapchain(old_swapchain);
 
         let swapchain = unsafe {
             swapchain_loader.create_swapchain(&create_info, None)
@@ -174,20 +213,27 @@ impl AurenSwapchain {
                 handle
             }
             Err(e) => {
-                log_err(&format!("Failed to create swap chain: {}", e));
-                panic!("Vulkan Error"); 
+                return Err(log_err(AurenError::vulkan("create swap chain", e)));
             }
         };
 
+        let destroy_swapchain = |err: AurenError| {
+            unsafe { swapchain_loader.destroy_swapchain(swapchain_handle, None) };
+            log_err(err)
+        };
+
         let swapchain_images = unsafe {
             swapchain_loader
                 .get_swapchain_images(swapchain_handle)
-                .expect("Failed to get swapchain images!")
+                .map_err(|e| destroy_swapchain(AurenError::vulkan("get swap chain images", e)))?
         };
 
         // Check if it's empty (though Vulkan shouldn't allow this if creation succeeded)
         if swapchain_images.is_empty() {
-            log_err("Vulkan driver returned zero swap chain images after creation!");
+            return Err(destroy_swapchain(AurenError::vulkan(
+                "get swap chain images",
+                vk::Result::ERROR_INITIALIZATION_FAILED,
+            )));
         }
 
         log_info(&format!(
@@ -215,30 +261,282 @@ impl AurenSwapchain {
                     layer_count: 1,
                 });
 
-            let view = unsafe {
-                device.create_image_view(&create_info, None)
-                    .expect("Failed to create image view!")
-            };
-            swapchain_image_views.push(view);
+            let view = unsafe { device.create_image_view(&create_info, None) };
+            match view {
+                Ok(view) => swapchain_image_views.push(view),
+                Err(e) => {
+                    for &view in swapchain_image_views.iter() {
+                        unsafe { device.destroy_image_view(view, None) };
+                    }
+                    return Err(destroy_swapchain(AurenError::vulkan("create image view", e)));
+                }
+            }
         }
 
-        Self {
-            support_details,
-            vsync_enabled,
-            surface_khr,
+        let mut swapchain = Self {
             swapchain_images,
             swapchain_extent,
-            swapchain_image_format,
             swapchain_image_views,
             swapchain_khr: swapchain_handle,
-            
-            swapchain_frame_buffer: Vec::new(),
-            swapchain_framebuffer: Vec::new(),
-            command_buffers: Vec::new(),
-            
-            render_pass: vk::RenderPass::null(),
+
             command_pool: vk::CommandPool::null(),
-            index_buffer: vk::Buffer::null(),
+            command_buffers: Vec::new(),
+
+            image_available: Vec::new(),
+            render_finished: Vec::new(),
+            in_flight: Vec::new(),
+            current_frame: 0,
+            clear_color: [0.0, 0.0, 0.0, 1.0],
+            can_clear,
+        };
+
+        if let Err(e) = swapchain.create_frame_resources(device, queue_families.graphics) {
+            swapchain.destroy(device, swapchain_loader);
+            return Err(log_err(e));
+        }
+
+        Ok(swapchain)
+    }
+
+    // Command buffers and synchronisation for presenting, created next to the swap chain
+    fn create_frame_resources(&mut self, device: &ash::Device, graphics_family: u32) -> Result<(), AurenError> {
+        let pool_info = vk::CommandPoolCreateInfo::default()
+            .flags(vk::CommandPoolCreateFlags::RESET_COMMAND_BUFFER)
+            .queue_family_index(graphics_family);
+
+        unsafe {
+            self.command_pool = device.create_command_pool(&pool_info, None)
+                .map_err(|e| AurenError::vulkan("create command pool", e))?;
+
+            let allocate_info = vk::CommandBufferAllocateInfo::default()
+                .command_pool(self.command_pool)
+                .level(vk::CommandBufferLevel::PRIMARY)
+                .command_buffer_count(FRAMES_IN_FLIGHT as u32);
+            self.command_buffers = device.allocate_command_buffers(&allocate_info)
+                .map_err(|e| AurenError::vulkan("allocate command buffers", e))?;
+
+            let semaphore_info = vk::SemaphoreCreateInfo::default();
+            // Signalled, so the first wait of every frame returns at once
+            let fence_info = vk::FenceCreateInfo::default().flags(vk::FenceCreateFlags::SIGNALED);
+
+            for _ in 0..FRAMES_IN_FLIGHT {
+                self.image_available.push(device.create_semaphore(&semaphore_info, None)
+                    .map_err(|e| AurenError::vulkan("create semaphore", e))?);
+                self.in_flight.push(device.create_fence(&fence_info, None)
+                    .map_err(|e| AurenError::vulkan("create fence", e))?);
+            }
+
+            for _ in 0..self.swapchain_images.len() {
+                self.render_finished.push(device.create_semaphore(&semaphore_info, None)
+                    .map_err(|e| AurenError::vulkan("create semaphore", e))?);
+            }
+        }
+
+        Ok(())
+    }
+
+    // Acquires the next image, clears it and presents it
+    pub fn present(
+        &mut self,
+        device: &ash::Device,
+        swapchain_loader: &khr::swapchain::Device,
+        queues: &DeviceQueues,
+    ) -> Result<PresentStatus, AurenError> {
+        let frame = self.current_frame;
+        let fence = self.in_flight[frame];
+
+        unsafe {
+            device.wait_for_fences(&[fence], true, u64::MAX)
+                .map_err(|e| AurenError::vulkan("wait for frame fence", e))?;
+
+            let acquired = swapchain_loader.acquire_next_image(
+                self.swapchain_khr,
+                u64::MAX,
+                self.image_available[frame],
+                vk::Fence::null(),
+            );
+            let (image_index, suboptimal) = match acquired {
+                Ok(acquired) => acquired,
+                Err(vk::Result::ERROR_OUT_OF_DATE_KHR) => return Ok(PresentStatus::OutOfDate),
+                Err(e) => return Err(AurenError::vulkan("acquire swap chain image", e)),
+            };
+
+            let command_buffer = self.command_buffers[frame];
+            let wait_semaphores = [self.image_available[frame]];
+            let wait_stages = [vk::PipelineStageFlags::TRANSFER];
+            let signal_semaphores = [self.render_finished[image_index as usize]];
+            let command_buffers = [command_buffer];
+
+            let submitted = self.record_commands(device, command_buffer, self.swapchain_images[image_index as usize])
+                .and_then(|()| {
+                    // Reset right before the submit, an early return would leave it unsignalled forever
+                    device.reset_fences(&[fence])
+                        .map_err(|e| AurenError::vulkan("reset frame fence", e))?;
+
+                    let submit_info = vk::SubmitInfo::default()
+                        .wait_semaphores(&wait_semaphores)
+                        .wait_dst_stage_mask(&wait_stages)
+                        .command_buffers(&command_buffers)
+                        .signal_semaphores(&signal_semaphores);
+
+                    device.queue_submit(queues.graphics, &[submit_info], fence)
+                        .map_err(|e| AurenError::vulkan("submit frame", e))
+                });
+
+            if let Err(e) = submitted {
+                self.release_image_available(device, queues, frame);
+                return Err(e);
+            }
+
+            self.current_frame = (frame + 1) % FRAMES_IN_FLIGHT;
+
+            let swapchains = [self.swapchain_khr];
+            let image_indices = [image_index];
+            let present_info = vk::PresentInfoKHR::default()
+                .wait_semaphores(&signal_semaphores)
+                .swapchains(&swapchains)
+                .image_indices(&image_indices);
+
+            match swapchain_loader.queue_present(queues.present, &present_info) {
+                Ok(false) if !suboptimal => Ok(PresentStatus::Presented),
+                Ok(_) => Ok(PresentStatus::Suboptimal),
+                Err(vk::Result::ERROR_OUT_OF_DATE_KHR) => Ok(PresentStatus::OutOfDate),
+                Err(e) => Err(AurenError::vulkan("present swap chain image", e)),
+            }
+        }
+    }
+
+    // Waits on the semaphore of an acquire whose frame was never submitted,
+    // since acquiring with a semaphore that is still signalled is invalid
+    fn release_image_available(&self, device: &ash::Device, queues: &DeviceQueues, frame: usize) {
+        let wait_semaphores = [self.image_available[frame]];
+        let wait_stages = [vk::PipelineStageFlags::ALL_COMMANDS];
+        let submit_info = vk::SubmitInfo::default()
+            .wait_semaphores(&wait_semaphores)
+            .wait_dst_stage_mask(&wait_stages);
+
+        if let Err(e) = unsafe { device.queue_submit(queues.graphics, &[submit_info], vk::Fence::null()) } {
+            log_err(AurenError::vulkan("release acquire semaphore", e));
+        }
+    }
+
+    // Clears the image and moves it into the layout the presentation engine expects
+    fn record_commands(&self, device: &ash::Device, command_buffer: vk::CommandBuffer, image: vk::Image) -> Result<(), AurenError> {
+        let range = vk::ImageSubresourceRange {
+            aspect_mask: vk::ImageAspectFlags::COLOR,
+            base_mip_level: 0,
+            level_count: 1,
+            base_array_layer: 0,
+            layer_count: 1,
+        };
+
+        let barrier = |old_layout, new_layout, src_access, dst_access| {
+            vk::ImageMemoryBarrier::default()
+                .old_layout(old_layout)
+                .new_layout(new_layout)
+                .src_access_mask(src_access)
+                .dst_access_mask(dst_access)
+                .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                .image(image)
+                .subresource_range(range)
+        };
+
+        unsafe {
+            device.reset_command_buffer(command_buffer, vk::CommandBufferResetFlags::empty())
+                .map_err(|e| AurenError::vulkan("reset command buffer", e))?;
+
+            let begin_info = vk::CommandBufferBeginInfo::default()
+                .flags(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT);
+            device.begin_command_buffer(command_buffer, &begin_info)
+                .map_err(|e| AurenError::vulkan("begin command buffer", e))?;
+
+            let present_layout = if self.can_clear {
+                let to_transfer = barrier(
+                    vk::ImageLayout::UNDEFINED,
+                    vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                    vk::AccessFlags::empty(),
+                    vk::AccessFlags::TRANSFER_WRITE,
+                );
+                device.cmd_pipeline_barrier(
+                    command_buffer,
+                    vk::PipelineStageFlags::TRANSFER,
+                    vk::PipelineStageFlags::TRANSFER,
+                    vk::DependencyFlags::empty(),
+                    &[],
+                    &[],
+                    &[to_transfer],
+                );
+
+                let clear_color = vk::ClearColorValue { float32: self.clear_color };
+                device.cmd_clear_color_image(
+                    command_buffer,
+                    image,
+                    vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                    &clear_color,
+                    &[range],
+                );
+
+                barrier(
+                    vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                    vk::ImageLayout::PRESENT_SRC_KHR,
+                    vk::AccessFlags::TRANSFER_WRITE,
+                    vk::AccessFlags::empty(),
+                )
+            } else {
+                barrier(
+                    vk::ImageLayout::UNDEFINED,
+                    vk::ImageLayout::PRESENT_SRC_KHR,
+                    vk::AccessFlags::empty(),
+                    vk::AccessFlags::empty(),
+                )
+            };
+
+            device.cmd_pipeline_barrier(
+                command_buffer,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::PipelineStageFlags::BOTTOM_OF_PIPE,
+                vk::DependencyFlags::empty(),
+                &[],
+                &[],
+                &[present_layout],
+            );
+
+            device.end_command_buffer(command_buffer)
+                .map_err(|e| AurenError::vulkan("end command buffer", e))?;
+        }
+
+        Ok(())
+    }
+
+    // Destroys everything but the surface, which belongs to the window. The device must be idle.
+    pub fn destroy(&mut self, device: &ash::Device, swapchain_loader: &khr::swapchain::Device) {
+        unsafe {
+            for fence in self.in_flight.drain(..) {
+                device.destroy_fence(fence, None);
+            }
+            for semaphore in self.image_available.drain(..).chain(self.render_finished.drain(..)) {
+                device.destroy_semaphore(semaphore, None);
+            }
+
+            // Frees the command buffers with it
+            if self.command_pool != vk::CommandPool::null() {
+                device.destroy_command_pool(self.command_pool, None);
+                self.command_pool = vk::CommandPool::null();
+                self.command_buffers.clear();
+            }
+
+            for view in self.swapchain_image_views.drain(..) {
+                device.destroy_image_view(view, None);
+            }
+
+            if self.swapchain_khr != vk::SwapchainKHR::null() {
+                swapchain_loader.destroy_swapchain(self.swapchain_khr, None);
+                self.swapchain_khr = vk::SwapchainKHR::null();
+            }
         }
+
+        self.swapchain_images.clear();
+        log_info("Swap chain destroyed");
     }
 }
\ No newline at end of file
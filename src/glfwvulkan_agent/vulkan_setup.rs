@@ -1,33 +1,326 @@
-use ash::{vk, Entry, Instance, khr};
-use vk::EXT_DEBUG_UTILS_NAME as NAME;
-use vk::EXT_DEBUG_UTILS_SPEC_VERSION as SPEC_VERSION;
+use ash::{ext::debug_utils, khr, vk, Entry, Instance};
+use std::ffi::{c_void, CStr, CString};
+use std::sync::Mutex;
+use crate::error::AurenError;
+use crate::logging::target;
+
+const VALIDATION_LAYER: &CStr = c"VK_LAYER_KHRONOS_validation";
+
+// What happens when the validation layer reports an error
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DebugErrorAction {
+    #[default]
+    Log,
+    // Logs the error, then panics once the Vulkan call that caused it has returned
+    Panic,
+    // Logs the error, then triggers a debugger breakpoint. Falls back to Log on unsupported architectures.
+    Break,
+}
+
+// Validation and debug output of the Vulkan instance. By default both are on in debug builds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VulkanDebugConfig {
+    // Enables VK_LAYER_KHRONOS_validation when it is installed
+    pub validation: bool,
+    // Routes validation and driver messages into the log, under target::VALIDATION
+    pub messenger: bool,
+    // Also forwards the verbose and info messages of the driver
+    pub verbose: bool,
+    pub on_error: DebugErrorAction,
+}
+
+impl Default for VulkanDebugConfig {
+    fn default() -> Self {
+        Self {
+            validation: cfg!(debug_assertions),
+            messenger: cfg!(debug_assertions),
+            verbose: false,
+            on_error: DebugErrorAction::default(),
+        }
+    }
+}
+
+impl VulkanDebugConfig {
+    pub fn disabled() -> Self {
+        Self {
+            validation: false,
+            messenger: false,
+            verbose: false,
+            on_error: DebugErrorAction::Log,
+        }
+    }
+
+    pub fn validation(mut self, validation: bool) -> Self {
+        self.validation = validation;
+        self
+    }
+
+    pub fn messenger(mut self, messenger: bool) -> Self {
+        self.messenger = messenger;
+        self
+    }
+
+    pub fn verbose(mut self, verbose: bool) -> Self {
+        self.verbose = verbose;
+        self
+    }
+
+    pub fn on_error(mut self, on_error: DebugErrorAction) -> Self {
+        self.on_error = on_error;
+        self
+    }
+}
+
+struct DebugMessenger {
+    loader: debug_utils::Instance,
+    handle: vk::DebugUtilsMessengerEXT,
+}
+
+// Shared with the callback through its user data pointer. A panic can't unwind out of
+// the callback, so it only records the error for check_validation_errors to raise.
+struct DebugUserData {
+    on_error: DebugErrorAction,
+    pending_error: Mutex<Option<String>>,
+}
 
 pub struct AurenVulkanSetup {
+    // Keeps the Vulkan loader loaded for as long as the instance exists
+    #[allow(dead_code)]
+    pub entry: Entry,
     pub instance: Instance,
+    pub surface_loader: khr::surface::Instance,
+    pub debug_config: VulkanDebugConfig,
+    debug_messenger: Option<DebugMessenger>,
+    // Boxed so its address survives moves, dropped after the instance as the messenger chained
+    // into create_instance still reports through it while vkDestroyInstance runs
+    debug_user_data: Box<DebugUserData>,
+}
+
+unsafe extern "system" fn debug_callback(
+    severity: vk::DebugUtilsMessageSeverityFlagsEXT,
+    message_type: vk::DebugUtilsMessageTypeFlagsEXT,
+    callback_data: *const vk::DebugUtilsMessengerCallbackDataEXT<'_>,
+    user_data: *mut c_void,
+) -> vk::Bool32 {
+    let (id_name, message) = match unsafe { callback_data.as_ref() } {
+        Some(data) => unsafe {
+            (
+                data.message_id_name_as_c_str().map(|s| s.to_string_lossy()).unwrap_or_default(),
+                data.message_as_c_str().map(|s| s.to_string_lossy()).unwrap_or_default(),
+            )
+        },
+        None => Default::default(),
+    };
+
+    let level = if severity.contains(vk::DebugUtilsMessageSeverityFlagsEXT::ERROR) {
+        log::Level::Error
+    } else if severity.contains(vk::DebugUtilsMessageSeverityFlagsEXT::WARNING) {
+        log::Level::Warn
+    } else if severity.contains(vk::DebugUtilsMessageSeverityFlagsEXT::INFO) {
+        log::Level::Debug
+    } else {
+        log::Level::Trace
+    };
+
+    log::log!(target: target::VALIDATION, level, "[{:?}] {}: {}", message_type, id_name, message);
+
+    if level == log::Level::Error
+        && let Some(user_data) = unsafe { (user_data as *const DebugUserData).as_ref() }
+    {
+        match user_data.on_error {
+            DebugErrorAction::Log => {}
+            DebugErrorAction::Panic => {
+                // Keep the first error, later ones are usually caused by it
+                if let Ok(mut pending) = user_data.pending_error.lock() {
+                    pending.get_or_insert_with(|| format!("{}: {}", id_name, message));
+                }
+            }
+            DebugErrorAction::Break => debug_break(),
+        }
+    }
+
+    // The Vulkan call that caused the message must not be aborted
+    vk::FALSE
+}
+
+fn debug_break() {
+    #[cfg(target_arch = "x86_64")]
+    unsafe { std::arch::asm!("int3") };
+    #[cfg(target_arch = "aarch64")]
+    unsafe { std::arch::asm!("brk #0xf000") };
+}
+
+fn messenger_create_info<'a>(config: &VulkanDebugConfig, user_data: &'a DebugUserData) -> vk::DebugUtilsMessengerCreateInfoEXT<'a> {
+    let mut severity = vk::DebugUtilsMessageSeverityFlagsEXT::WARNING | vk::DebugUtilsMessageSeverityFlagsEXT::ERROR;
+    if config.verbose {
+        severity |= vk::DebugUtilsMessageSeverityFlagsEXT::VERBOSE | vk::DebugUtilsMessageSeverityFlagsEXT::INFO;
+    }
+
+    vk::DebugUtilsMessengerCreateInfoEXT::default()
+        .message_severity(severity)
+        .message_type(
+            vk::DebugUtilsMessageTypeFlagsEXT::GENERAL
+                | vk::DebugUtilsMessageTypeFlagsEXT::VALIDATION
+                | vk::DebugUtilsMessageTypeFlagsEXT::PERFORMANCE,
+        )
+        .pfn_user_callback(Some(debug_callback))
+        .user_data(user_data as *const DebugUserData as *mut c_void)
 }
 
 impl AurenVulkanSetup {
-    pub fn new() -> Self {
-        let entry = unsafe { Entry::load().expect("Failed to load Vulkan library") };
+    // `window_extensions` are the instance extensions the window system needs for its surfaces
+    pub fn new(debug_config: VulkanDebugConfig, window_extensions: &[String]) -> Result<Self, AurenError> {
+        let entry = unsafe { Entry::load() }
+            .map_err(|e| AurenError::MissingVulkanLoader(e.to_string()))?;
+
+        let validation_enabled = debug_config.validation && {
+            let layers = unsafe { entry.enumerate_instance_layer_properties() }
+                .map_err(|e| AurenError::vulkan("enumerate instance layers", e))?;
+            let available = layers.iter().any(|layer| layer.layer_name_as_c_str() == Ok(VALIDATION_LAYER));
+            if !available {
+                log::warn!(target: target::VULKAN, "Validation requested, but {:?} is not installed", VALIDATION_LAYER);
+            }
+            available
+        };
+
+        let messenger_enabled = debug_config.messenger && {
+            let extensions = unsafe { entry.enumerate_instance_extension_properties(None) }
+                .map_err(|e| AurenError::vulkan("enumerate instance extensions", e))?;
+            let available = extensions.iter().any(|ext| ext.extension_name_as_c_str() == Ok(debug_utils::NAME));
+            if !available {
+                log::warn!(target: target::VULKAN, "Debug messenger requested, but {:?} is not available", debug_utils::NAME);
+            }
+            available
+        };
 
         let app_info = vk::ApplicationInfo::default()
-            .application_name(unsafe { std::ffi::CStr::from_bytes_with_nul_unchecked(b"AurenFox App\0") })
+            .application_name(c"AurenFox App")
             .application_version(0)
-            .engine_name(unsafe { std::ffi::CStr::from_bytes_with_nul_unchecked(b"AurenFox Engine\0") })
+            .engine_name(c"AurenFox Engine")
             .engine_version(0)
             .api_version(vk::API_VERSION_1_3);
 
-        let create_info = vk::InstanceCreateInfo::default()
-            .application_info(&app_info);
+        let mut layers = Vec::new();
+        if validation_enabled {
+            layers.push(VALIDATION_LAYER.as_ptr());
+        }
+
+        let window_extensions: Vec<CString> = window_extensions
+            .iter()
+            .filter_map(|name| CString::new(name.as_str()).ok())
+            .collect();
+
+        let mut extensions: Vec<_> = window_extensions.iter().map(|name| name.as_ptr()).collect();
+        if messenger_enabled {
+            extensions.push(debug_utils::NAME.as_ptr());
+        }
+
+        let debug_user_data = Box::new(DebugUserData {
+            on_error: debug_config.on_error,
+            pending_error: Mutex::new(None),
+        });
+        // Chained into the instance as well, to catch mistakes in create_instance itself
+        let mut instance_messenger_info = messenger_create_info(&debug_config, &debug_user_data);
+
+        let mut create_info = vk::InstanceCreateInfo::default()
+            .application_info(&app_info)
+            .enabled_layer_names(&layers)
+            .enabled_extension_names(&extensions);
+        if messenger_enabled {
+            create_info = create_info.push_next(&mut instance_messenger_info);
+        }
 
         let instance = unsafe {
-            Entry::load().expect("Vulkan library not found!")
-                .create_instance(&create_info, None)
-                .expect("Failed to create Vulkan instance")
+            entry.create_instance(&create_info, None)
+                .map_err(|e| AurenError::vulkan("create Vulkan instance", e))?
         };
 
-        Self {
+        log::info!(target: target::VULKAN, "Vulkan instance created");
+
+        let debug_messenger = if messenger_enabled {
+            let loader = debug_utils::Instance::new(&entry, &instance);
+            let handle = unsafe {
+                loader.create_debug_utils_messenger(&messenger_create_info(&debug_config, &debug_user_data), None)
+            };
+
+            match handle {
+                Ok(handle) => Some(DebugMessenger { loader, handle }),
+                Err(e) => {
+                    unsafe { instance.destroy_instance(None) };
+                    return Err(AurenError::vulkan("create debug messenger", e));
+                }
+            }
+        } else {
+            None
+        };
+
+        if validation_enabled {
+            log::info!(target: target::VULKAN, "Validation layer enabled");
+        }
+
+        let surface_loader = khr::surface::Instance::new(&entry, &instance);
+
+        Ok(Self {
+            entry,
             instance,
+            surface_loader,
+            debug_config,
+            debug_messenger,
+            debug_user_data,
+        })
+    }
+
+    // Raises a validation error recorded with DebugErrorAction::Panic. Call it after the
+    // Vulkan calls of a step returned, the callback itself must not unwind.
+    pub fn check_validation_errors(&self) {
+        let pending = self.debug_user_data.pending_error.lock().ok().and_then(|mut pending| pending.take());
+        if let Some(error) = pending {
+            panic!("Vulkan validation error {}", error);
+        }
+    }
+}
+
+impl Drop for AurenVulkanSetup {
+    fn drop(&mut self) {
+        if let Some(messenger) = self.debug_messenger.take() {
+            unsafe { messenger.loader.destroy_debug_utils_messenger(messenger.handle, None) };
         }
+
+        // Every device and surface of the instance has to be destroyed by now.
+        // debug_user_data is only dropped after this, once the fields go.
+        unsafe { self.instance.destroy_instance(None) };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(severity: vk::DebugUtilsMessageSeverityFlagsEXT, user_data: &DebugUserData) -> vk::Bool32 {
+        let user_data = user_data as *const DebugUserData as *mut c_void;
+        unsafe { debug_callback(severity, vk::DebugUtilsMessageTypeFlagsEXT::VALIDATION, std::ptr::null(), user_data) }
     }
-}
\ No newline at end of file
+
+    fn user_data(on_error: DebugErrorAction) -> DebugUserData {
+        DebugUserData { on_error, pending_error: Mutex::new(None) }
+    }
+
+    #[test]
+    fn panic_is_deferred_until_after_the_callback() {
+        let data = user_data(DebugErrorAction::Panic);
+
+        assert_eq!(report(vk::DebugUtilsMessageSeverityFlagsEXT::WARNING, &data), vk::FALSE);
+        assert!(data.pending_error.lock().unwrap().is_none());
+
+        assert_eq!(report(vk::DebugUtilsMessageSeverityFlagsEXT::ERROR, &data), vk::FALSE);
+        assert!(data.pending_error.lock().unwrap().is_some());
+    }
+
+    #[test]
+    fn log_never_records_errors() {
+        let data = user_data(DebugErrorAction::Log);
+        report(vk::DebugUtilsMessageSeverityFlagsEXT::ERROR, &data);
+
+        assert!(data.pending_error.lock().unwrap().is_none());
+    }
+}
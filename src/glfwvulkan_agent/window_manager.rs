@@ -1,83 +1,311 @@
 use glfw::{PWindow};
+use std::{cell::RefCell, rc::Rc, time::Duration};
+use crate::error::AurenError;
+use crate::interfaces::{AurenEvent, ClientApi, Cursor, CursorMode, StandardCursor, Fullscreen, MonitorInfo, VideoMode, WindowDescriptor, WindowId, WindowIdAllocator, WindowMode};
+use crate::logging::target;
+use super::events::translate_event;
 
 extern crate glfw;
 
+type WindowEvents = glfw::GlfwReceiver<(f64, glfw::WindowEvent)>;
+// A freshly opened window with its events, mode and windowed position
+type OpenedWindow = (PWindow, WindowEvents, WindowMode, Option<(i32, i32)>);
+
 pub struct AurenWindow {
     pub window: PWindow,
-    pub events: glfw::GlfwReceiver<(f64, glfw::WindowEvent)>,
+    pub events: WindowEvents,
     pub title: String,
+    // Size in screen coordinates
     pub width: u32,
     pub height: u32,
-    pub id: usize,
+    // Size in pixels, what the swapchain extent is built from
+    pub framebuffer_width: u32,
+    pub framebuffer_height: u32,
+    pub content_scale: (f32, f32),
+    pub id: WindowId,
+    pub client_api: ClientApi,
+    pub mode: WindowMode,
+    pub decorated: bool,
+    // Placement to return to when leaving fullscreen, as (x, y, width, height)
+    pub windowed_rect: (i32, i32, u32, u32),
 }
 
 pub struct AurenWindowManager {
     pub glfw: glfw::Glfw,
     pub windows: Vec<AurenWindow>,
+    pub ids: WindowIdAllocator,
+    pub frame_events: Vec<AurenEvent>,
+    // Filled by the GLFW monitor callback as (monitor name, connected)
+    monitor_changes: Rc<RefCell<Vec<(String, bool)>>>,
+}
+
+fn translate_video_mode(mode: &glfw::VidMode) -> VideoMode {
+    VideoMode {
+        width: mode.width,
+        height: mode.height,
+        red_bits: mode.red_bits,
+        green_bits: mode.green_bits,
+        blue_bits: mode.blue_bits,
+        refresh_rate: mode.refresh_rate,
+    }
+}
+
+fn monitor_info(index: usize, monitor: &glfw::Monitor) -> MonitorInfo {
+    let (width_mm, height_mm) = monitor.get_physical_size();
+    let (x, y, width, height) = monitor.get_workarea();
+
+    MonitorInfo {
+        index,
+        name: monitor.get_name().unwrap_or_default(),
+        physical_size: (width_mm.max(0) as u32, height_mm.max(0) as u32),
+        position: monitor.get_pos(),
+        work_area: (x, y, width.max(0) as u32, height.max(0) as u32),
+        content_scale: monitor.get_content_scale(),
+        current_mode: monitor.get_video_mode().as_ref().map(translate_video_mode),
+        video_modes: monitor.get_video_modes().iter().map(translate_video_mode).collect(),
+    }
+}
+
+// Translates a backend neutral cursor into a GLFW cursor
+fn create_cursor(cursor: &Cursor) -> glfw::Cursor {
+    match cursor {
+        Cursor::Standard(shape) => glfw::Cursor::standard(match shape {
+            StandardCursor::Arrow => glfw::StandardCursor::Arrow,
+            StandardCursor::IBeam => glfw::StandardCursor::IBeam,
+            StandardCursor::Crosshair => glfw::StandardCursor::Crosshair,
+            StandardCursor::Hand => glfw::StandardCursor::Hand,
+            StandardCursor::ResizeHorizontal => glfw::StandardCursor::HResize,
+            StandardCursor::ResizeVertical => glfw::StandardCursor::VResize,
+        }),
+        Cursor::Custom(image) => {
+            // GLFW reads each u32 as the RGBA bytes in memory order
+            let pixels = image.pixels()
+                .chunks_exact(4)
+                .map(|rgba| u32::from_ne_bytes([rgba[0], rgba[1], rgba[2], rgba[3]]))
+                .collect();
+            let (x_hotspot, y_hotspot) = image.hotspot();
+            glfw::Cursor::create_from_pixels(
+                glfw::PixelImage { width: image.width(), height: image.height(), pixels },
+                x_hotspot,
+                y_hotspot,
+            )
+        }
+    }
+}
+
+// Top left corner that centers a window of this size in the work area of the monitor
+fn centered_position(monitor: &glfw::Monitor, width: u32, height: u32) -> (i32, i32) {
+    let (x, y, area_width, area_height) = monitor.get_workarea();
+    (
+        x + (area_width - width.min(i32::MAX as u32) as i32).max(0) / 2,
+        y + (area_height - height.min(i32::MAX as u32) as i32).max(0) / 2,
+    )
 }
 
 impl AurenWindowManager {
-    pub fn new() -> Self {
-        let mut glfw = glfw::init(glfw::fail_on_errors).unwrap();
-        
-        // Good practice: Set window hints before creation
-        glfw.window_hint(glfw::WindowHint::ContextVersion(3, 3));
-        glfw.window_hint(glfw::WindowHint::OpenGlProfile(glfw::OpenGlProfileHint::Core));
-
-        AurenWindowManager {
+    pub fn new() -> Result<Self, AurenError> {
+        // Some requests (e.g. positioning a window on Wayland) aren't supported
+        // everywhere, so GLFW errors are logged instead of ending the program
+        let mut glfw = glfw::init(|error, description| {
+            log::error!(target: target::WINDOW, "GLFW error {:?}: {}", error, description);
+        })
+        .map_err(|e| AurenError::WindowSystemInit(e.to_string()))?;
+
+        let monitor_changes = Rc::new(RefCell::new(Vec::new()));
+        let callback_changes = Rc::clone(&monitor_changes);
+        glfw.set_monitor_callback(move |monitor, event| {
+            let name = monitor.get_name().unwrap_or_default();
+            callback_changes.borrow_mut().push((name, event == glfw::MonitorEvent::Connected));
+        });
+
+        Ok(AurenWindowManager {
             glfw,
             windows: Vec::new(),
-        }
+            ids: WindowIdAllocator::new(),
+            frame_events: Vec::new(),
+            monitor_changes,
+        })
     }
     
-    pub fn create_window(&mut self, title: &str, width: u32, height: u32, id: Option<usize>) -> Result<usize, String> {
-        let (mut window, events) = self.glfw
-            .create_window(width, height, title, glfw::WindowMode::Windowed)
-            .ok_or_else(|| format!("Failed to create GLFW window with title: '{}'", title))?;
+    fn apply_window_hints(&mut self, descriptor: &WindowDescriptor) {
+        self.glfw.default_window_hints();
 
-        window.set_key_polling(true);
-
-        let final_id: usize = match id {
-            Some(provided_id) => {
-                if self.windows.iter().any(|w| w.id == provided_id) {
-                    return Err(format!("Window ID {} is already in use!", provided_id));
+        match descriptor.client_api {
+            ClientApi::NoApi => {
+                self.glfw.window_hint(glfw::WindowHint::ClientApi(glfw::ClientApiHint::NoApi));
+            }
+            ClientApi::OpenGl { major, minor } => {
+                self.glfw.window_hint(glfw::WindowHint::ClientApi(glfw::ClientApiHint::OpenGl));
+                self.glfw.window_hint(glfw::WindowHint::ContextVersion(major, minor));
+                if (major, minor) >= (3, 2) {
+                    self.glfw.window_hint(glfw::WindowHint::OpenGlProfile(glfw::OpenGlProfileHint::Core));
                 }
-                provided_id
-            },
-            None => {
-                let mut ids: Vec<usize> = self.windows.iter().map(|w| w.id).collect();
-                ids.sort_unstable();
-
-                ids.iter().enumerate()
-                    .position(|(i, &id)| i != id)
-                    .unwrap_or(ids.len())
-            },
+            }
+        }
+
+        self.glfw.window_hint(glfw::WindowHint::Resizable(descriptor.resizable));
+        self.glfw.window_hint(glfw::WindowHint::Decorated(descriptor.decorated));
+        self.glfw.window_hint(glfw::WindowHint::TransparentFramebuffer(descriptor.transparent));
+        self.glfw.window_hint(glfw::WindowHint::Floating(descriptor.floating));
+        self.glfw.window_hint(glfw::WindowHint::Maximized(descriptor.maximized));
+        self.glfw.window_hint(glfw::WindowHint::ScaleToMonitor(descriptor.scale_to_monitor));
+
+        // A window that still has to be moved is shown once it is in place
+        let show_on_creation = descriptor.visible && descriptor.position.is_none();
+        self.glfw.window_hint(glfw::WindowHint::Visible(show_on_creation));
+    }
+
+    // Creates the GLFW window itself
+    fn open_window(&mut self, descriptor: &WindowDescriptor) -> Result<OpenedWindow, AurenError> {
+        self.apply_window_hints(descriptor);
+
+        let title = descriptor.title.as_str();
+        let (width, height) = (descriptor.width, descriptor.height);
+
+        let (created, mode, windowed_position) = match descriptor.fullscreen {
+            None => (
+                self.glfw.create_window(width, height, title, glfw::WindowMode::Windowed),
+                WindowMode::Windowed,
+                descriptor.position,
+            ),
+            Some(fullscreen) => {
+                // GLFW always lists the primary monitor first
+                let index = match fullscreen {
+                    Fullscreen::PrimaryMonitor => 0,
+                    Fullscreen::Monitor(index) => index,
+                };
+
+                let (created, position) = self.glfw.with_connected_monitors(|glfw, monitors| {
+                    monitors.get(index).map(|monitor| {
+                        let position = centered_position(monitor, width, height);
+                        (glfw.create_window(width, height, title, glfw::WindowMode::FullScreen(monitor)), position)
+                    })
+                }).ok_or(AurenError::MonitorNotFound(index))?;
+
+                (created, WindowMode::ExclusiveFullscreen { monitor: index, video_mode: None }, Some(position))
+            }
         };
 
+        let (window, events) = created
+            .ok_or_else(|| AurenError::WindowCreation(title.to_string()))?;
+
+        Ok((window, events, mode, windowed_position))
+    }
+
+    pub fn create_window(&mut self, descriptor: &WindowDescriptor, id: Option<usize>) -> Result<WindowId, AurenError> {
+        // Checked before any OS window exists, and given back if none could be made
+        let final_id = self.ids.allocate(id)?;
+        let (mut window, events, mode, windowed_position) = self.open_window(descriptor)
+            .inspect_err(|_| {
+                self.ids.free(final_id);
+            })?;
+
+        let title = descriptor.title.as_str();
+        let (width, height) = (descriptor.width, descriptor.height);
+        let (x, y) = windowed_position.unwrap_or_else(|| window.get_pos());
+
+        if descriptor.min_size.is_some() || descriptor.max_size.is_some() {
+            window.set_size_limits(
+                descriptor.min_size.map(|(w, _)| w),
+                descriptor.min_size.map(|(_, h)| h),
+                descriptor.max_size.map(|(w, _)| w),
+                descriptor.max_size.map(|(_, h)| h),
+            );
+        }
+
+        if let Some((numerator, denominator)) = descriptor.aspect_ratio {
+            window.set_aspect_ratio(numerator, denominator);
+        }
+
+        if let Some((x, y)) = descriptor.position {
+            if descriptor.fullscreen.is_none() {
+                window.set_pos(x, y);
+            }
+            if descriptor.visible {
+                window.show();
+            }
+        }
+
+        window.set_key_polling(true);
+        // Delivers the same characters as char polling, with the modifiers held while typing
+        window.set_char_mods_polling(true);
+        window.set_mouse_button_polling(true);
+        window.set_cursor_pos_polling(true);
+        window.set_scroll_polling(true);
+        window.set_focus_polling(true);
+        window.set_size_polling(true);
+        window.set_framebuffer_size_polling(true);
+        window.set_content_scale_polling(true);
+        window.set_close_polling(true);
+        window.set_drag_and_drop_polling(true);
+
+        // Scaling to the monitor or a HiDPI display can change the requested size
+        let (logical_width, logical_height) = window.get_size();
+        let (framebuffer_width, framebuffer_height) = window.get_framebuffer_size();
+        let content_scale = window.get_content_scale();
+
         self.windows.push(AurenWindow {
             window,
             events,
             title: title.to_string(),
-            width,
-            height,
+            width: logical_width.max(0) as u32,
+            height: logical_height.max(0) as u32,
+            framebuffer_width: framebuffer_width.max(0) as u32,
+            framebuffer_height: framebuffer_height.max(0) as u32,
+            content_scale,
             id: final_id,
+            client_api: descriptor.client_api,
+            mode,
+            decorated: descriptor.decorated,
+            windowed_rect: (x, y, width, height),
         });
 
+        log::debug!(target: target::WINDOW, "Created window {} '{}' ({}x{})", final_id, title, width, height);
+
         Ok(final_id)
     }
 
     pub fn update(&mut self) {
         // We poll events via the glfw handle
         self.glfw.poll_events();
+        self.frame_events.clear();
+
+        let time = self.glfw.get_time();
+        for (name, connected) in self.monitor_changes.borrow_mut().drain(..) {
+            log::info!(target: target::WINDOW, "Monitor '{}' {}", name, if connected { "connected" } else { "disconnected" });
+            self.frame_events.push(if connected {
+                AurenEvent::MonitorConnected { time, name }
+            } else {
+                AurenEvent::MonitorDisconnected { time, name }
+            });
+        }
 
         for window in &mut self.windows {
-            // "Draining" the events satisfies the compiler for the `events` field
-            for (_, event) in glfw::flush_messages(&window.events) {
+            for (time, event) in glfw::flush_messages(&window.events) {
+                if let Some(auren_event) = translate_event(window.id, time, &event) {
+                    self.frame_events.push(auren_event);
+                }
+
                 match event {
+                    // Translated here, the cursor position has to be read from the window
+                    glfw::WindowEvent::FileDrop(paths) => {
+                        self.frame_events.push(AurenEvent::FilesDropped {
+                            window: window.id,
+                            time,
+                            paths,
+                            cursor_pos: window.window.get_cursor_pos(),
+                        });
+                    }
+                    glfw::WindowEvent::Size(w, h) => {
+                        window.width = w.max(0) as u32;
+                        window.height = h.max(0) as u32;
+                    }
                     glfw::WindowEvent::FramebufferSize(w, h) => {
-                        // Now width and height are being "read" and updated!
-                        window.width = w as u32;
-                        window.height = h as u32;
+                        window.framebuffer_width = w.max(0) as u32;
+                        window.framebuffer_height = h.max(0) as u32;
+                    }
+                    glfw::WindowEvent::ContentScale(x_scale, y_scale) => {
+                        window.content_scale = (x_scale, y_scale);
                     }
                     glfw::WindowEvent::Close => {
                         window.window.set_should_close(true);
@@ -88,18 +316,143 @@ impl AurenWindowManager {
         }
     }
 
-    #[allow(dead_code)]
-    pub fn check_for_id(&self, id: usize) -> bool {
-        return self.windows.iter().any(|w| w.id == id)
+    pub fn monitors(&mut self) -> Vec<MonitorInfo> {
+        self.glfw.with_connected_monitors(|_, monitors| {
+            monitors.iter()
+                .enumerate()
+                .map(|(index, monitor)| monitor_info(index, monitor))
+                .collect()
+        })
     }
 
-    #[allow(dead_code)]
-    pub fn get_window_by_id(&mut self, id: usize) -> Option<&mut AurenWindow> {
-        return self.windows.iter_mut().find(|w| w.id == id)
+    pub fn set_window_mode(&mut self, id: WindowId, mode: WindowMode) -> Result<(), AurenError> {
+        self.ids.validate(id)?;
+        let window = self.windows.iter_mut()
+            .find(|w| w.id == id)
+            .ok_or(AurenError::UnknownWindow(id))?;
+
+        if window.mode == WindowMode::Windowed {
+            let (x, y) = window.window.get_pos();
+            let (width, height) = window.window.get_size();
+            window.windowed_rect = (x, y, width.max(0) as u32, height.max(0) as u32);
+        }
+
+        match mode {
+            WindowMode::Windowed => {
+                let (x, y, width, height) = window.windowed_rect;
+                window.window.set_monitor(glfw::WindowMode::Windowed, x, y, width, height, None);
+                window.window.set_decorated(window.decorated);
+            }
+            WindowMode::BorderlessFullscreen { monitor } => {
+                let (x, y, video_mode) = self.glfw.with_connected_monitors(|_, monitors| {
+                    monitors.get(monitor).and_then(|m| {
+                        let (x, y) = m.get_pos();
+                        m.get_video_mode().map(|video_mode| (x, y, video_mode))
+                    })
+                }).ok_or(AurenError::MonitorNotFound(monitor))?;
+
+                window.window.set_decorated(false);
+                window.window.set_monitor(glfw::WindowMode::Windowed, x, y, video_mode.width, video_mode.height, None);
+            }
+            WindowMode::ExclusiveFullscreen { monitor, video_mode } => {
+                self.glfw.with_connected_monitors(|_, monitors| {
+                    let m = monitors.get(monitor)?;
+                    let video_mode = video_mode.or_else(|| m.get_video_mode().as_ref().map(translate_video_mode))?;
+                    window.window.set_monitor(
+                        glfw::WindowMode::FullScreen(m),
+                        0,
+                        0,
+                        video_mode.width,
+                        video_mode.height,
+                        Some(video_mode.refresh_rate),
+                    );
+                    Some(())
+                }).ok_or(AurenError::MonitorNotFound(monitor))?;
+            }
+        }
+
+        window.mode = mode;
+        Ok(())
+    }
+
+    pub fn cursor_mode(&self, id: WindowId) -> Result<CursorMode, AurenError> {
+        Ok(match self.window(id)?.window.get_cursor_mode() {
+            glfw::CursorMode::Normal => CursorMode::Normal,
+            glfw::CursorMode::Hidden => CursorMode::Hidden,
+            _ => CursorMode::Captured,
+        })
+    }
+
+    pub fn set_cursor_mode(&mut self, id: WindowId, mode: CursorMode) -> Result<(), AurenError> {
+        let raw_motion = self.glfw.supports_raw_motion();
+        let window = &mut self.window_mut(id)?.window;
+
+        match mode {
+            CursorMode::Normal => window.set_cursor_mode(glfw::CursorMode::Normal),
+            CursorMode::Hidden => window.set_cursor_mode(glfw::CursorMode::Hidden),
+            CursorMode::Captured => window.set_cursor_mode(glfw::CursorMode::Disabled),
+        }
+
+        // Raw motion skips the acceleration of the desktop, only useful while captured
+        if raw_motion {
+            window.set_raw_mouse_motion(mode == CursorMode::Captured);
+        }
+        Ok(())
+    }
+
+    // The window keeps the GLFW cursor alive until it is replaced
+    pub fn set_cursor(&mut self, id: WindowId, cursor: &Cursor) -> Result<(), AurenError> {
+        self.window_mut(id)?.window.set_cursor(Some(create_cursor(cursor)));
+        Ok(())
+    }
+
+    pub fn wait_events(&mut self, timeout: Option<Duration>) {
+        match timeout {
+            Some(timeout) => self.glfw.wait_events_timeout(timeout.as_secs_f64()),
+            None => self.glfw.wait_events(),
+        }
+    }
+
+    pub fn check_for_id(&self, id: WindowId) -> bool {
+        self.ids.is_alive(id)
     }
 
     #[allow(dead_code)]
-    pub fn destroy_window(&mut self, id: usize) {
+    pub fn get_window_by_id(&mut self, id: WindowId) -> Option<&mut AurenWindow> {
+        self.windows.iter_mut().find(|w| w.id == id)
+    }
+
+    pub fn window(&self, id: WindowId) -> Result<&AurenWindow, AurenError> {
+        self.ids.validate(id)?;
+        self.windows.iter().find(|w| w.id == id).ok_or(AurenError::UnknownWindow(id))
+    }
+
+    pub fn window_mut(&mut self, id: WindowId) -> Result<&mut AurenWindow, AurenError> {
+        self.ids.validate(id)?;
+        self.get_window_by_id(id).ok_or(AurenError::UnknownWindow(id))
+    }
+
+    pub fn destroy_window(&mut self, id: WindowId) -> Result<(), AurenError> {
+        self.ids.validate(id)?;
+        self.ids.free(id);
         self.windows.retain(|win| win.id != id );
+        Ok(())
+    }
+
+    // Windows whose close was requested, dropped by the next cleanup_closed_windows
+    pub fn closed_window_ids(&self) -> Vec<WindowId> {
+        self.windows.iter().filter(|w| w.window.should_close()).map(|w| w.id).collect()
+    }
+
+    // Drops the windows whose close was requested and frees their ids
+    pub fn cleanup_closed_windows(&mut self) {
+        let ids = &mut self.ids;
+        self.windows.retain(|w| {
+            let closed = w.window.should_close();
+            if closed {
+                ids.free(w.id);
+            }
+            !closed
+        });
     }
 }
\ No newline at end of file
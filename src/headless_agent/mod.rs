@@ -0,0 +1,667 @@
+// Uses
+
+use crate::error::AurenError;
+use crate::interfaces::{AurenEvent, AurenModifiers, Cursor, CursorMode, JoystickState, MonitorInfo, RHI, VideoMode, WindowDescriptor, WindowId, WindowIdAllocator, WindowMode, WindowState};
+use std::{cell::RefCell, path::PathBuf, rc::Rc, time::{Duration, Instant}};
+
+// Structures
+
+struct HeadlessWindow {
+    title: String,
+    width: u32,
+    height: u32,
+    content_scale: (f32, f32),
+    position: (i32, i32),
+    state: WindowState,
+    mode: WindowMode,
+    windowed_size: (u32, u32),
+    attention_requested: bool,
+    cursor_mode: CursorMode,
+    cursor: Cursor,
+    id: WindowId,
+    should_close: bool,
+}
+
+struct HeadlessState {
+    windows: Vec<HeadlessWindow>,
+    ids: WindowIdAllocator,
+    // Frame and window slot, as the ids may not exist yet when the close is scripted
+    scripted_closes: Vec<(u64, usize)>,
+    queued_events: Vec<AurenEvent>,
+    frame_events: Vec<AurenEvent>,
+    monitors: Vec<MonitorInfo>,
+    clipboard: Option<String>,
+    joysticks: Vec<JoystickState>,
+    started: Instant,
+
+    primary_window_id: Option<WindowId>,
+    frame_index: u64,
+    frame_limit: Option<u64>,
+    program_should_end: bool,
+}
+
+// A backend without a display or GPU. Windows only exist in memory, which makes
+// it possible to drive the framework from tests and CI machines.
+#[derive(Default)]
+pub struct HeadlessAgent {
+    state: Rc<RefCell<HeadlessState>>,
+}
+
+// Shared view into a HeadlessAgent that stays usable after the agent has been
+// handed to the framework. Clone it into the user code to script the run.
+#[derive(Clone)]
+pub struct HeadlessHandle {
+    state: Rc<RefCell<HeadlessState>>,
+}
+
+// Implementations
+
+// The single 1920x1080 monitor every HeadlessAgent starts with
+fn default_monitor() -> MonitorInfo {
+    let mode = VideoMode {
+        width: 1920,
+        height: 1080,
+        red_bits: 8,
+        green_bits: 8,
+        blue_bits: 8,
+        refresh_rate: 60,
+    };
+
+    MonitorInfo {
+        index: 0,
+        name: "Headless Monitor".to_string(),
+        physical_size: (527, 296),
+        position: (0, 0),
+        work_area: (0, 0, 1920, 1080),
+        content_scale: (1.0, 1.0),
+        current_mode: Some(mode),
+        video_modes: vec![mode],
+    }
+}
+
+impl Default for HeadlessState {
+    fn default() -> Self {
+        Self {
+            windows: Vec::new(),
+            ids: WindowIdAllocator::new(),
+            scripted_closes: Vec::new(),
+            queued_events: Vec::new(),
+            frame_events: Vec::new(),
+            monitors: vec![default_monitor()],
+            clipboard: None,
+            joysticks: Vec::new(),
+            started: Instant::now(),
+
+            primary_window_id: None,
+            frame_index: 0,
+            frame_limit: None,
+            program_should_end: false,
+        }
+    }
+}
+
+impl HeadlessAgent {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Ends the program once this many frames have run, so a test can never hang
+    pub fn with_frame_limit(self, frames: u64) -> Self {
+        self.state.borrow_mut().frame_limit = Some(frames);
+        self
+    }
+
+    // Simulates the user pressing the close button during the given frame, for the
+    // window created with this named id
+    pub fn close_at_frame(self, frame: u64, id: usize) -> Self {
+        self.state.borrow_mut().scripted_closes.push((frame, id));
+        self
+    }
+
+    // Replaces the simulated monitors, the first one is the primary monitor
+    pub fn with_monitors(self, monitors: Vec<MonitorInfo>) -> Self {
+        let mut state = self.state.borrow_mut();
+        state.monitors = monitors;
+        state.reindex_monitors();
+        drop(state);
+        self
+    }
+
+    pub fn handle(&self) -> HeadlessHandle {
+        HeadlessHandle {
+            state: Rc::clone(&self.state),
+        }
+    }
+}
+
+impl HeadlessWindow {
+    // The framebuffer follows the logical size at the content scale, like on macOS
+    fn framebuffer_size(&self) -> (u32, u32) {
+        (
+            (self.width as f32 * self.content_scale.0).round() as u32,
+            (self.height as f32 * self.content_scale.1).round() as u32,
+        )
+    }
+}
+
+impl HeadlessState {
+    fn cleanup_closed_windows(&mut self) {
+        let ids = &mut self.ids;
+        self.windows.retain(|w| {
+            if w.should_close {
+                ids.free(w.id);
+            }
+            !w.should_close
+        });
+    }
+
+    fn check_for_id(&self, id: WindowId) -> bool {
+        self.ids.is_alive(id)
+    }
+
+    fn reindex_monitors(&mut self) {
+        for (index, monitor) in self.monitors.iter_mut().enumerate() {
+            monitor.index = index;
+        }
+    }
+
+    fn window(&self, id: WindowId) -> Result<&HeadlessWindow, AurenError> {
+        self.ids.validate(id)?;
+        self.windows.iter().find(|w| w.id == id).ok_or(AurenError::UnknownWindow(id))
+    }
+
+    fn window_mut(&mut self, id: WindowId) -> Result<&mut HeadlessWindow, AurenError> {
+        self.ids.validate(id)?;
+        self.windows.iter_mut().find(|w| w.id == id).ok_or(AurenError::UnknownWindow(id))
+    }
+
+    fn time(&self) -> f64 {
+        self.started.elapsed().as_secs_f64()
+    }
+
+    // Reports a new logical size on the next frame, with the matching framebuffer size
+    fn queue_resize(&mut self, id: WindowId, width: u32, height: u32) -> Result<(), AurenError> {
+        let scale = self.window(id)?.content_scale;
+        let time = self.time();
+        self.queued_events.push(AurenEvent::Resized { window: id, time, width, height });
+        self.queued_events.push(AurenEvent::FramebufferResized {
+            window: id,
+            time,
+            width: (width as f32 * scale.0).round() as u32,
+            height: (height as f32 * scale.1).round() as u32,
+        });
+        Ok(())
+    }
+
+    fn update(&mut self) {
+        let frame = self.frame_index;
+        let time = self.time();
+        let scripted: Vec<AurenEvent> = self.scripted_closes
+            .iter()
+            .filter(|(at, _)| *at == frame)
+            .filter_map(|&(_, index)| self.windows.iter().find(|w| w.id.index() == index))
+            .map(|w| AurenEvent::CloseRequested { window: w.id, time })
+            .collect();
+        self.scripted_closes.retain(|(at, _)| *at > frame);
+        self.queued_events.extend(scripted);
+
+        self.frame_events.clear();
+        for event in std::mem::take(&mut self.queued_events) {
+            let Some(id) = event.window() else {
+                self.frame_events.push(event);
+                continue;
+            };
+            let Some(window) = self.windows.iter_mut().find(|w| w.id == id) else {
+                continue;
+            };
+
+            match event {
+                AurenEvent::Resized { width, height, .. } => {
+                    window.width = width;
+                    window.height = height;
+                }
+                AurenEvent::ScaleChanged { x_scale, y_scale, .. } => {
+                    window.content_scale = (x_scale, y_scale);
+                }
+                AurenEvent::Focused { focused, .. } => {
+                    window.state.focused = focused;
+                }
+                AurenEvent::CloseRequested { .. } => {
+                    window.should_close = true;
+                }
+                _ => {}
+            }
+
+            self.frame_events.push(event);
+        }
+    }
+}
+
+impl HeadlessHandle {
+    // Simulates the user pressing the close button, picked up on the next frame
+    pub fn request_close(&self, id: WindowId) {
+        let mut state = self.state.borrow_mut();
+        let time = state.time();
+        state.queued_events.push(AurenEvent::CloseRequested { window: id, time });
+    }
+
+    // Queues an event as if the user produced it, delivered on the next frame
+    pub fn push_event(&self, event: AurenEvent) {
+        self.state.borrow_mut().queued_events.push(event);
+    }
+
+    // Queues a Char event for every character, as if the text was typed into the window
+    pub fn type_text(&self, id: WindowId, text: &str) {
+        let mut state = self.state.borrow_mut();
+        let time = state.time();
+        state.queued_events.extend(text.chars().map(|character| AurenEvent::Char {
+            window: id,
+            time,
+            character,
+            modifiers: AurenModifiers::default(),
+        }));
+    }
+
+    // Simulates the user dropping files onto the window at the given cursor position
+    pub fn drop_files(&self, id: WindowId, paths: Vec<PathBuf>, cursor_pos: (f64, f64)) {
+        let mut state = self.state.borrow_mut();
+        let time = state.time();
+        state.queued_events.push(AurenEvent::FilesDropped { window: id, time, paths, cursor_pos });
+    }
+
+    // Current timestamp of the simulated clock, for building events
+    pub fn time(&self) -> f64 {
+        self.state.borrow().time()
+    }
+
+    pub fn close_at_frame(&self, frame: u64, id: WindowId) {
+        self.state.borrow_mut().scripted_closes.push((frame, id.index()));
+    }
+
+    // Simulates moving the window to a monitor with another content scale. The
+    // ScaleChanged and FramebufferResized events are delivered on the next frame.
+    pub fn set_content_scale(&self, id: WindowId, x_scale: f32, y_scale: f32) {
+        let mut state = self.state.borrow_mut();
+        let Ok(window) = state.window(id) else { return };
+        let width = (window.width as f32 * x_scale).round() as u32;
+        let height = (window.height as f32 * y_scale).round() as u32;
+        let time = state.time();
+        state.queued_events.push(AurenEvent::ScaleChanged { window: id, time, x_scale, y_scale });
+        state.queued_events.push(AurenEvent::FramebufferResized { window: id, time, width, height });
+    }
+
+    // Simulates another application copying text, read back by get_clipboard_text
+    pub fn set_clipboard_text(&self, text: &str) {
+        self.state.borrow_mut().clipboard = Some(text.to_string());
+    }
+
+    pub fn clipboard_text(&self) -> Option<String> {
+        self.state.borrow().clipboard.clone()
+    }
+
+    // Simulates plugging in a joystick, announced with a JoystickConnected event.
+    // Replaces a joystick already connected at the same index.
+    pub fn connect_joystick(&self, joystick: JoystickState) {
+        let mut state = self.state.borrow_mut();
+        let time = state.time();
+        let (index, name) = (joystick.index, joystick.name.clone());
+        state.joysticks.retain(|j| j.index != index);
+        state.joysticks.push(joystick);
+        state.queued_events.push(AurenEvent::JoystickConnected { time, joystick: index, name });
+    }
+
+    // Changes the axes and buttons of a connected joystick, read on the next frame
+    pub fn set_joystick(&self, joystick: JoystickState) {
+        let mut state = self.state.borrow_mut();
+        if let Some(slot) = state.joysticks.iter_mut().find(|j| j.index == joystick.index) {
+            *slot = joystick;
+        }
+    }
+
+    pub fn disconnect_joystick(&self, index: usize) {
+        let mut state = self.state.borrow_mut();
+        if !state.joysticks.iter().any(|j| j.index == index) {
+            return;
+        }
+
+        let time = state.time();
+        state.joysticks.retain(|j| j.index != index);
+        state.queued_events.push(AurenEvent::JoystickDisconnected { time, joystick: index });
+    }
+
+    // Simulates plugging in a monitor, announced with a MonitorConnected event
+    pub fn connect_monitor(&self, monitor: MonitorInfo) {
+        let mut state = self.state.borrow_mut();
+        let time = state.time();
+        let name = monitor.name.clone();
+        state.monitors.push(monitor);
+        state.reindex_monitors();
+        state.queued_events.push(AurenEvent::MonitorConnected { time, name });
+    }
+
+    pub fn disconnect_monitor(&self, index: usize) {
+        let mut state = self.state.borrow_mut();
+        if index >= state.monitors.len() {
+            return;
+        }
+
+        let time = state.time();
+        let name = state.monitors.remove(index).name;
+        state.reindex_monitors();
+        state.queued_events.push(AurenEvent::MonitorDisconnected { time, name });
+    }
+
+    pub fn window_ids(&self) -> Vec<WindowId> {
+        self.state.borrow().windows.iter().map(|w| w.id).collect()
+    }
+
+    pub fn window_count(&self) -> usize {
+        self.state.borrow().windows.len()
+    }
+
+    pub fn has_window(&self, id: WindowId) -> bool {
+        self.state.borrow().check_for_id(id)
+    }
+
+    pub fn window_title(&self, id: WindowId) -> Option<String> {
+        self.state.borrow().window(id).ok().map(|w| w.title.clone())
+    }
+
+    pub fn window_size(&self, id: WindowId) -> Option<(u32, u32)> {
+        self.state.borrow().window(id).ok().map(|w| (w.width, w.height))
+    }
+
+    pub fn framebuffer_size(&self, id: WindowId) -> Option<(u32, u32)> {
+        self.state.borrow().window(id).ok().map(|w| w.framebuffer_size())
+    }
+
+    pub fn window_position(&self, id: WindowId) -> Option<(i32, i32)> {
+        self.state.borrow().window(id).ok().map(|w| w.position)
+    }
+
+    pub fn window_mode(&self, id: WindowId) -> Option<WindowMode> {
+        self.state.borrow().window(id).ok().map(|w| w.mode)
+    }
+
+    pub fn window_state(&self, id: WindowId) -> Option<WindowState> {
+        self.state.borrow().window(id).ok().map(|w| w.state)
+    }
+
+    // Whether request_window_attention was called for the window
+    pub fn attention_requested(&self, id: WindowId) -> bool {
+        self.state.borrow().window(id).is_ok_and(|w| w.attention_requested)
+    }
+
+    pub fn cursor(&self, id: WindowId) -> Option<Cursor> {
+        self.state.borrow().window(id).ok().map(|w| w.cursor.clone())
+    }
+
+    pub fn primary_window(&self) -> Option<WindowId> {
+        self.state.borrow().primary_window_id
+    }
+
+    pub fn frame_index(&self) -> u64 {
+        self.state.borrow().frame_index
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.state.borrow().program_should_end
+    }
+}
+
+impl RHI for HeadlessAgent {
+    fn reinitialize(&mut self) -> Result<(), AurenError> {
+        let mut state = self.state.borrow_mut();
+        // Free instead of resetting the ids, so handles from before stay dead
+        for window in std::mem::take(&mut state.windows) {
+            state.ids.free(window.id);
+        }
+        state.queued_events.clear();
+        state.frame_events.clear();
+        state.primary_window_id = None;
+        state.frame_index = 0;
+        state.program_should_end = false;
+        Ok(())
+    }
+
+    fn assign_master(&mut self, id: WindowId) -> Result<(), AurenError> {
+        let mut state = self.state.borrow_mut();
+        state.ids.validate(id)?;
+        state.primary_window_id = Some(id);
+        Ok(())
+    }
+
+    fn validate_window(&self, id: WindowId) -> Result<(), AurenError> {
+        self.state.borrow().ids.validate(id)
+    }
+
+    fn create_window(&mut self, descriptor: &WindowDescriptor, id: Option<usize>) -> Result<WindowId, AurenError> {
+        let mut state = self.state.borrow_mut();
+        let final_id = state.ids.allocate(id)?;
+        let content_scale = state.monitors.first().map_or((1.0, 1.0), |m| m.content_scale);
+
+        state.windows.push(HeadlessWindow {
+            title: descriptor.title.clone(),
+            width: descriptor.width,
+            height: descriptor.height,
+            content_scale,
+            position: descriptor.position.unwrap_or((0, 0)),
+            state: WindowState {
+                minimized: false,
+                maximized: descriptor.maximized,
+                visible: descriptor.visible,
+                focused: false,
+            },
+            mode: WindowMode::Windowed,
+            windowed_size: (descriptor.width, descriptor.height),
+            attention_requested: false,
+            cursor_mode: CursorMode::default(),
+            cursor: Cursor::default(),
+            id: final_id,
+            should_close: false,
+        });
+
+        Ok(final_id)
+    }
+
+    fn start_frame(&mut self) {
+        let mut state = self.state.borrow_mut();
+        state.cleanup_closed_windows();
+
+        if state.windows.is_empty() {
+            state.program_should_end = true;
+            return;
+        }
+
+        // Check if the master window is set
+        if state.primary_window_id.is_some_and(|id| !state.check_for_id(id)) {
+            state.program_should_end = true;
+            return;
+        }
+
+        if state.frame_limit.is_some_and(|limit| state.frame_index >= limit) {
+            state.program_should_end = true;
+            return;
+        }
+
+        state.update();
+    }
+
+    fn end_frame(&mut self) {
+        let mut state = self.state.borrow_mut();
+        if state.program_should_end { return; }
+        state.frame_index += 1;
+    }
+
+    fn destroy_window(&mut self, id: WindowId) -> Result<(), AurenError> {
+        let mut state = self.state.borrow_mut();
+        state.ids.validate(id)?;
+        state.ids.free(id);
+        state.windows.retain(|w| w.id != id);
+        Ok(())
+    }
+
+    fn should_close(&self) -> bool {
+        self.state.borrow().program_should_end
+    }
+
+    fn window_title(&self, id: WindowId) -> Result<String, AurenError> {
+        Ok(self.state.borrow().window(id)?.title.clone())
+    }
+
+    fn set_window_title(&mut self, id: WindowId, title: &str) -> Result<(), AurenError> {
+        self.state.borrow_mut().window_mut(id)?.title = title.to_string();
+        Ok(())
+    }
+
+    fn window_size(&self, id: WindowId) -> Result<(u32, u32), AurenError> {
+        let state = self.state.borrow();
+        let window = state.window(id)?;
+        Ok((window.width, window.height))
+    }
+
+    fn framebuffer_size(&self, id: WindowId) -> Result<(u32, u32), AurenError> {
+        Ok(self.state.borrow().window(id)?.framebuffer_size())
+    }
+
+    fn content_scale(&self, id: WindowId) -> Result<(f32, f32), AurenError> {
+        Ok(self.state.borrow().window(id)?.content_scale)
+    }
+
+    // Like a real window, the new size is reported by a Resized event on the next frame
+    fn set_window_size(&mut self, id: WindowId, width: u32, height: u32) -> Result<(), AurenError> {
+        self.state.borrow_mut().queue_resize(id, width, height)
+    }
+
+    fn window_position(&self, id: WindowId) -> Result<(i32, i32), AurenError> {
+        Ok(self.state.borrow().window(id)?.position)
+    }
+
+    fn set_window_position(&mut self, id: WindowId, x: i32, y: i32) -> Result<(), AurenError> {
+        self.state.borrow_mut().window_mut(id)?.position = (x, y);
+        Ok(())
+    }
+
+    fn window_state(&self, id: WindowId) -> Result<WindowState, AurenError> {
+        Ok(self.state.borrow().window(id)?.state)
+    }
+
+    fn minimize_window(&mut self, id: WindowId) -> Result<(), AurenError> {
+        self.state.borrow_mut().window_mut(id)?.state.minimized = true;
+        Ok(())
+    }
+
+    fn maximize_window(&mut self, id: WindowId) -> Result<(), AurenError> {
+        let mut state = self.state.borrow_mut();
+        let window = state.window_mut(id)?;
+        window.state.minimized = false;
+        window.state.maximized = true;
+        Ok(())
+    }
+
+    fn restore_window(&mut self, id: WindowId) -> Result<(), AurenError> {
+        let mut state = self.state.borrow_mut();
+        let window = state.window_mut(id)?;
+        window.state.minimized = false;
+        window.state.maximized = false;
+        Ok(())
+    }
+
+    fn show_window(&mut self, id: WindowId) -> Result<(), AurenError> {
+        self.state.borrow_mut().window_mut(id)?.state.visible = true;
+        Ok(())
+    }
+
+    fn hide_window(&mut self, id: WindowId) -> Result<(), AurenError> {
+        self.state.borrow_mut().window_mut(id)?.state.visible = false;
+        Ok(())
+    }
+
+    fn focus_window(&mut self, id: WindowId) -> Result<(), AurenError> {
+        let mut state = self.state.borrow_mut();
+        state.window(id)?;
+        for window in &mut state.windows {
+            window.state.focused = window.id == id;
+        }
+        Ok(())
+    }
+
+    fn request_window_attention(&mut self, id: WindowId) -> Result<(), AurenError> {
+        self.state.borrow_mut().window_mut(id)?.attention_requested = true;
+        Ok(())
+    }
+
+    fn cursor_mode(&self, id: WindowId) -> Result<CursorMode, AurenError> {
+        Ok(self.state.borrow().window(id)?.cursor_mode)
+    }
+
+    fn set_cursor_mode(&mut self, id: WindowId, mode: CursorMode) -> Result<(), AurenError> {
+        self.state.borrow_mut().window_mut(id)?.cursor_mode = mode;
+        Ok(())
+    }
+
+    fn set_cursor(&mut self, id: WindowId, cursor: &Cursor) -> Result<(), AurenError> {
+        self.state.borrow_mut().window_mut(id)?.cursor = cursor.clone();
+        Ok(())
+    }
+
+    // The clipboard lives in memory and works without windows
+    fn get_clipboard_text(&self) -> Option<String> {
+        self.state.borrow().clipboard.clone()
+    }
+
+    fn set_clipboard_text(&mut self, text: &str) -> Result<(), AurenError> {
+        self.state.borrow_mut().clipboard = Some(text.to_string());
+        Ok(())
+    }
+
+    fn monitors(&mut self) -> Vec<MonitorInfo> {
+        self.state.borrow().monitors.clone()
+    }
+
+    fn window_mode(&self, id: WindowId) -> Result<WindowMode, AurenError> {
+        Ok(self.state.borrow().window(id)?.mode)
+    }
+
+    // Fullscreen windows take the size of the video mode, reported by a Resized event
+    fn set_window_mode(&mut self, id: WindowId, mode: WindowMode) -> Result<(), AurenError> {
+        let mut state = self.state.borrow_mut();
+        let size = match mode {
+            WindowMode::Windowed => None,
+            WindowMode::BorderlessFullscreen { monitor } | WindowMode::ExclusiveFullscreen { monitor, video_mode: None } => {
+                let monitor = state.monitors.get(monitor).ok_or(AurenError::MonitorNotFound(monitor))?;
+                monitor.current_mode.map(|m| (m.width, m.height))
+            }
+            WindowMode::ExclusiveFullscreen { monitor, video_mode: Some(video_mode) } => {
+                state.monitors.get(monitor).ok_or(AurenError::MonitorNotFound(monitor))?;
+                Some((video_mode.width, video_mode.height))
+            }
+        };
+
+        let window = state.window_mut(id)?;
+        if window.mode == WindowMode::Windowed {
+            window.windowed_size = (window.width, window.height);
+        }
+        window.mode = mode;
+
+        let (width, height) = size.unwrap_or(window.windowed_size);
+        state.queue_resize(id, width, height)
+    }
+
+    fn poll_joysticks(&mut self) -> Vec<JoystickState> {
+        self.state.borrow().joysticks.clone()
+    }
+
+    // There are no physical joysticks to map, the simulated ones come with their gamepad state
+    fn update_gamepad_mappings(&mut self, _mappings: &str) -> Result<(), AurenError> {
+        Ok(())
+    }
+
+    // Nothing arrives from outside the process, so waiting would only stall tests.
+    // Events pushed through a HeadlessHandle are delivered by the next start_frame.
+    fn wait_events(&mut self, _timeout: Option<Duration>) {}
+
+    fn drain_events(&mut self) -> Vec<AurenEvent> {
+        std::mem::take(&mut self.state.borrow_mut().frame_events)
+    }
+}
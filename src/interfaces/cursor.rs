@@ -0,0 +1,124 @@
+use crate::error::AurenError;
+
+// Backend neutral cursor appearance and behaviour
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CursorMode {
+    #[default]
+    Normal,
+    // Invisible while over the content area, moves freely otherwise
+    Hidden,
+    // Hidden and locked to the window, with unbounded (and raw where supported) motion for camera controls
+    Captured,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StandardCursor {
+    #[default]
+    Arrow,
+    IBeam,
+    Crosshair,
+    Hand,
+    ResizeHorizontal,
+    ResizeVertical,
+}
+
+// Custom cursor image, RGBA with 8 bits per channel, rows from top to bottom
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CursorImage {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+    hotspot: (u32, u32),
+}
+
+impl CursorImage {
+    // The hotspot is the pixel that points at the cursor position, from the top left corner
+    pub fn new(width: u32, height: u32, pixels: Vec<u8>, hotspot_x: u32, hotspot_y: u32) -> Result<Self, AurenError> {
+        if width == 0 || height == 0 {
+            return Err(AurenError::InvalidCursorImage("the image is empty".to_string()));
+        }
+
+        let expected = width as usize * height as usize * 4;
+        if pixels.len() != expected {
+            return Err(AurenError::InvalidCursorImage(format!(
+                "expected {} bytes for {}x{} RGBA pixels, got {}",
+                expected, width, height, pixels.len()
+            )));
+        }
+
+        if hotspot_x >= width || hotspot_y >= height {
+            return Err(AurenError::InvalidCursorImage(format!(
+                "hotspot ({}, {}) lies outside the {}x{} image",
+                hotspot_x, hotspot_y, width, height
+            )));
+        }
+
+        Ok(Self {
+            width,
+            height,
+            pixels,
+            hotspot: (hotspot_x, hotspot_y),
+        })
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    pub fn hotspot(&self) -> (u32, u32) {
+        self.hotspot
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Cursor {
+    Standard(StandardCursor),
+    Custom(CursorImage),
+}
+
+impl Default for Cursor {
+    fn default() -> Self {
+        Cursor::Standard(StandardCursor::Arrow)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_matching_pixels_and_hotspot() {
+        let image = CursorImage::new(2, 3, vec![7; 24], 1, 2).unwrap();
+
+        assert_eq!((image.width(), image.height()), (2, 3));
+        assert_eq!(image.pixels().len(), 24);
+        assert_eq!(image.hotspot(), (1, 2));
+    }
+
+    #[test]
+    fn rejects_empty_images() {
+        assert!(matches!(CursorImage::new(0, 2, Vec::new(), 0, 0), Err(AurenError::InvalidCursorImage(_))));
+        assert!(matches!(CursorImage::new(2, 0, Vec::new(), 0, 0), Err(AurenError::InvalidCursorImage(_))));
+    }
+
+    #[test]
+    fn rejects_pixel_data_of_the_wrong_length() {
+        assert!(matches!(CursorImage::new(2, 2, vec![0; 15], 0, 0), Err(AurenError::InvalidCursorImage(_))));
+        assert!(matches!(CursorImage::new(2, 2, vec![0; 17], 0, 0), Err(AurenError::InvalidCursorImage(_))));
+    }
+
+    #[test]
+    fn rejects_hotspots_outside_the_image() {
+        assert!(matches!(CursorImage::new(2, 2, vec![0; 16], 2, 0), Err(AurenError::InvalidCursorImage(_))));
+        assert!(matches!(CursorImage::new(2, 2, vec![0; 16], 0, 2), Err(AurenError::InvalidCursorImage(_))));
+    }
+}
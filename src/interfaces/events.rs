@@ -0,0 +1,221 @@
+// Backend neutral input and window events
+
+use super::WindowId;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AurenKey {
+    Space,
+    Apostrophe,
+    Comma,
+    Minus,
+    Period,
+    Slash,
+    Num0,
+    Num1,
+    Num2,
+    Num3,
+    Num4,
+    Num5,
+    Num6,
+    Num7,
+    Num8,
+    Num9,
+    Semicolon,
+    Equal,
+    A,
+    B,
+    C,
+    D,
+    E,
+    F,
+    G,
+    H,
+    I,
+    J,
+    K,
+    L,
+    M,
+    N,
+    O,
+    P,
+    Q,
+    R,
+    S,
+    T,
+    U,
+    V,
+    W,
+    X,
+    Y,
+    Z,
+    LeftBracket,
+    Backslash,
+    RightBracket,
+    GraveAccent,
+    World1,
+    World2,
+    Escape,
+    Enter,
+    Tab,
+    Backspace,
+    Insert,
+    Delete,
+    Right,
+    Left,
+    Down,
+    Up,
+    PageUp,
+    PageDown,
+    Home,
+    End,
+    CapsLock,
+    ScrollLock,
+    NumLock,
+    PrintScreen,
+    Pause,
+    F1,
+    F2,
+    F3,
+    F4,
+    F5,
+    F6,
+    F7,
+    F8,
+    F9,
+    F10,
+    F11,
+    F12,
+    F13,
+    F14,
+    F15,
+    F16,
+    F17,
+    F18,
+    F19,
+    F20,
+    F21,
+    F22,
+    F23,
+    F24,
+    F25,
+    Kp0,
+    Kp1,
+    Kp2,
+    Kp3,
+    Kp4,
+    Kp5,
+    Kp6,
+    Kp7,
+    Kp8,
+    Kp9,
+    KpDecimal,
+    KpDivide,
+    KpMultiply,
+    KpSubtract,
+    KpAdd,
+    KpEnter,
+    KpEqual,
+    LeftShift,
+    LeftControl,
+    LeftAlt,
+    LeftSuper,
+    RightShift,
+    RightControl,
+    RightAlt,
+    RightSuper,
+    Menu,
+    Unknown,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AurenMouseButton {
+    Left,
+    Right,
+    Middle,
+    Other(u8),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AurenAction {
+    Press,
+    Release,
+    Repeat,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct AurenModifiers {
+    pub shift: bool,
+    pub control: bool,
+    pub alt: bool,
+    pub super_key: bool,
+    pub caps_lock: bool,
+    pub num_lock: bool,
+}
+
+// Every event carries the backend timestamp in seconds at which it was received.
+// All events except the monitor and joystick ones belong to a window and carry its id.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AurenEvent {
+    Key { window: WindowId, time: f64, key: AurenKey, scancode: i32, action: AurenAction, modifiers: AurenModifiers },
+    // Unicode character typed into the window, after keyboard layout and input method handling
+    Char { window: WindowId, time: f64, character: char, modifiers: AurenModifiers },
+    MouseButton { window: WindowId, time: f64, button: AurenMouseButton, action: AurenAction, modifiers: AurenModifiers },
+    CursorMoved { window: WindowId, time: f64, x: f64, y: f64 },
+    Scroll { window: WindowId, time: f64, x: f64, y: f64 },
+    Focused { window: WindowId, time: f64, focused: bool },
+    // Size in screen coordinates
+    Resized { window: WindowId, time: f64, width: u32, height: u32 },
+    // Size in pixels, may change without a Resized event when moving between monitors
+    FramebufferResized { window: WindowId, time: f64, width: u32, height: u32 },
+    ScaleChanged { window: WindowId, time: f64, x_scale: f32, y_scale: f32 },
+    CloseRequested { window: WindowId, time: f64 },
+    // Files dropped onto the window, cursor_pos is where they were released in window coordinates
+    FilesDropped { window: WindowId, time: f64, paths: Vec<PathBuf>, cursor_pos: (f64, f64) },
+    MonitorConnected { time: f64, name: String },
+    MonitorDisconnected { time: f64, name: String },
+    JoystickConnected { time: f64, joystick: usize, name: String },
+    JoystickDisconnected { time: f64, joystick: usize },
+}
+
+impl AurenEvent {
+    pub fn window(&self) -> Option<WindowId> {
+        match *self {
+            AurenEvent::Key { window, .. }
+            | AurenEvent::Char { window, .. }
+            | AurenEvent::MouseButton { window, .. }
+            | AurenEvent::CursorMoved { window, .. }
+            | AurenEvent::Scroll { window, .. }
+            | AurenEvent::Focused { window, .. }
+            | AurenEvent::Resized { window, .. }
+            | AurenEvent::FramebufferResized { window, .. }
+            | AurenEvent::ScaleChanged { window, .. }
+            | AurenEvent::CloseRequested { window, .. }
+            | AurenEvent::FilesDropped { window, .. } => Some(window),
+            AurenEvent::MonitorConnected { .. }
+            | AurenEvent::MonitorDisconnected { .. }
+            | AurenEvent::JoystickConnected { .. }
+            | AurenEvent::JoystickDisconnected { .. } => None,
+        }
+    }
+
+    pub fn time(&self) -> f64 {
+        match *self {
+            AurenEvent::Key { time, .. }
+            | AurenEvent::Char { time, .. }
+            | AurenEvent::MouseButton { time, .. }
+            | AurenEvent::CursorMoved { time, .. }
+            | AurenEvent::Scroll { time, .. }
+            | AurenEvent::Focused { time, .. }
+            | AurenEvent::Resized { time, .. }
+            | AurenEvent::FramebufferResized { time, .. }
+            | AurenEvent::ScaleChanged { time, .. }
+            | AurenEvent::CloseRequested { time, .. }
+            | AurenEvent::FilesDropped { time, .. }
+            | AurenEvent::MonitorConnected { time, .. }
+            | AurenEvent::MonitorDisconnected { time, .. }
+            | AurenEvent::JoystickConnected { time, .. }
+            | AurenEvent::JoystickDisconnected { time, .. } => time,
+        }
+    }
+}
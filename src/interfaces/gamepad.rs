@@ -0,0 +1,105 @@
+// Backend neutral joystick and gamepad state
+
+// Buttons of the standard gamepad layout, named after the Xbox controller
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GamepadButton {
+    A,
+    B,
+    X,
+    Y,
+    LeftBumper,
+    RightBumper,
+    Back,
+    Start,
+    Guide,
+    LeftThumb,
+    RightThumb,
+    DpadUp,
+    DpadRight,
+    DpadDown,
+    DpadLeft,
+}
+
+impl GamepadButton {
+    pub const ALL: [GamepadButton; 15] = [
+        GamepadButton::A,
+        GamepadButton::B,
+        GamepadButton::X,
+        GamepadButton::Y,
+        GamepadButton::LeftBumper,
+        GamepadButton::RightBumper,
+        GamepadButton::Back,
+        GamepadButton::Start,
+        GamepadButton::Guide,
+        GamepadButton::LeftThumb,
+        GamepadButton::RightThumb,
+        GamepadButton::DpadUp,
+        GamepadButton::DpadRight,
+        GamepadButton::DpadDown,
+        GamepadButton::DpadLeft,
+    ];
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GamepadAxis {
+    LeftX,
+    LeftY,
+    RightX,
+    RightY,
+    LeftTrigger,
+    RightTrigger,
+}
+
+impl GamepadAxis {
+    pub const ALL: [GamepadAxis; 6] = [
+        GamepadAxis::LeftX,
+        GamepadAxis::LeftY,
+        GamepadAxis::RightX,
+        GamepadAxis::RightY,
+        GamepadAxis::LeftTrigger,
+        GamepadAxis::RightTrigger,
+    ];
+}
+
+// Direction of a hat (d-pad on plain joysticks), all false when centered
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct HatState {
+    pub up: bool,
+    pub right: bool,
+    pub down: bool,
+    pub left: bool,
+}
+
+// A joystick mapped to the standard gamepad layout. Sticks range from -1.0 to 1.0
+// with positive y pointing down, triggers range from 0.0 (released) to 1.0.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct GamepadState {
+    pub name: String,
+    pub buttons: [bool; 15],
+    pub axes: [f32; 6],
+}
+
+impl GamepadState {
+    pub fn button(&self, button: GamepadButton) -> bool {
+        self.buttons[button as usize]
+    }
+
+    pub fn axis(&self, axis: GamepadAxis) -> f32 {
+        self.axes[axis as usize]
+    }
+}
+
+// Raw state of a connected joystick, as reported by the backend at the start of a frame
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct JoystickState {
+    // Slot of the joystick, stays the same while it is connected
+    pub index: usize,
+    pub name: String,
+    // SDL compatible GUID, used to look up gamepad mappings
+    pub guid: Option<String>,
+    pub axes: Vec<f32>,
+    pub buttons: Vec<bool>,
+    pub hats: Vec<HatState>,
+    // Present if a gamepad mapping is known for the joystick
+    pub gamepad: Option<GamepadState>,
+}
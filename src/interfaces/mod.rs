@@ -1,10 +1,27 @@
+mod cursor;
+mod events;
+mod gamepad;
+mod monitor;
+mod window;
+mod window_id;
+
+use crate::error::AurenError;
+use std::time::Duration;
+
+pub use cursor::{Cursor, CursorImage, CursorMode, StandardCursor};
+pub use events::{AurenAction, AurenEvent, AurenKey, AurenModifiers, AurenMouseButton};
+pub use gamepad::{GamepadAxis, GamepadButton, GamepadState, HatState, JoystickState};
+pub use monitor::{MonitorInfo, VideoMode, WindowMode};
+pub use window::{ClientApi, Fullscreen, WindowDescriptor, WindowMetrics, WindowState};
+pub use window_id::{MAX_WINDOWS, WindowId, WindowIdAllocator};
+
 // Render Hardware Interface
 pub trait RHI {
-    fn create_window(&mut self, title: &str, width: u32, height: u32, id: Option<usize>) -> Result<usize, String>;
+    fn create_window(&mut self, descriptor: &WindowDescriptor, id: Option<usize>) -> Result<WindowId, AurenError>;
 
-    fn new(&mut self);
+    fn reinitialize(&mut self) -> Result<(), AurenError>;
 
-    fn destroy_window(&mut self, id: usize);
+    fn destroy_window(&mut self, id: WindowId) -> Result<(), AurenError>;
 
     fn start_frame(&mut self);
 
@@ -12,5 +29,77 @@ pub trait RHI {
 
     fn should_close(&self) -> bool;
 
-    fn assign_master(&mut self, id: usize);
+    fn assign_master(&mut self, id: WindowId) -> Result<(), AurenError>;
+
+    // Fails with AurenError::UnknownWindow or AurenError::DeadWindowHandle if the id doesn't address a live window
+    fn validate_window(&self, id: WindowId) -> Result<(), AurenError>;
+
+    // Window control, every call validates the id like validate_window
+
+    fn window_title(&self, id: WindowId) -> Result<String, AurenError>;
+
+    fn set_window_title(&mut self, id: WindowId, title: &str) -> Result<(), AurenError>;
+
+    // Size in screen coordinates
+    fn window_size(&self, id: WindowId) -> Result<(u32, u32), AurenError>;
+
+    // Size in pixels, what a swapchain for the window has to match
+    fn framebuffer_size(&self, id: WindowId) -> Result<(u32, u32), AurenError>;
+
+    fn content_scale(&self, id: WindowId) -> Result<(f32, f32), AurenError>;
+
+    fn set_window_size(&mut self, id: WindowId, width: u32, height: u32) -> Result<(), AurenError>;
+
+    fn window_position(&self, id: WindowId) -> Result<(i32, i32), AurenError>;
+
+    fn set_window_position(&mut self, id: WindowId, x: i32, y: i32) -> Result<(), AurenError>;
+
+    fn window_state(&self, id: WindowId) -> Result<WindowState, AurenError>;
+
+    fn minimize_window(&mut self, id: WindowId) -> Result<(), AurenError>;
+
+    fn maximize_window(&mut self, id: WindowId) -> Result<(), AurenError>;
+
+    fn restore_window(&mut self, id: WindowId) -> Result<(), AurenError>;
+
+    fn show_window(&mut self, id: WindowId) -> Result<(), AurenError>;
+
+    fn hide_window(&mut self, id: WindowId) -> Result<(), AurenError>;
+
+    fn focus_window(&mut self, id: WindowId) -> Result<(), AurenError>;
+
+    fn request_window_attention(&mut self, id: WindowId) -> Result<(), AurenError>;
+
+    fn cursor_mode(&self, id: WindowId) -> Result<CursorMode, AurenError>;
+
+    fn set_cursor_mode(&mut self, id: WindowId, mode: CursorMode) -> Result<(), AurenError>;
+
+    // Shape of the cursor while it is over the content area of the window
+    fn set_cursor(&mut self, id: WindowId, cursor: &Cursor) -> Result<(), AurenError>;
+
+    // None if the clipboard is empty or holds something other than text
+    fn get_clipboard_text(&self) -> Option<String>;
+
+    fn set_clipboard_text(&mut self, text: &str) -> Result<(), AurenError>;
+
+    // Connected monitors, the primary monitor first
+    fn monitors(&mut self) -> Vec<MonitorInfo>;
+
+    fn window_mode(&self, id: WindowId) -> Result<WindowMode, AurenError>;
+
+    // Fails with AurenError::MonitorNotFound if the monitor of the mode isn't connected
+    fn set_window_mode(&mut self, id: WindowId, mode: WindowMode) -> Result<(), AurenError>;
+
+    // State of every connected joystick, read once per frame after start_frame
+    fn poll_joysticks(&mut self) -> Vec<JoystickState>;
+
+    // Adds or replaces gamepad mappings in the SDL_GameControllerDB format, one per line
+    fn update_gamepad_mappings(&mut self, mappings: &str) -> Result<(), AurenError>;
+
+    // Blocks until the backend receives an event or the timeout passes.
+    // The events are picked up by the following start_frame.
+    fn wait_events(&mut self, timeout: Option<Duration>);
+
+    // Hands over the events gathered during the last start_frame
+    fn drain_events(&mut self) -> Vec<AurenEvent>;
 }
\ No newline at end of file
@@ -0,0 +1,38 @@
+// Backend neutral description of the connected monitors
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VideoMode {
+    pub width: u32,
+    pub height: u32,
+    pub red_bits: u32,
+    pub green_bits: u32,
+    pub blue_bits: u32,
+    pub refresh_rate: u32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MonitorInfo {
+    // Position in the list of connected monitors, the primary monitor is always 0.
+    // Indices shift when a monitor is disconnected.
+    pub index: usize,
+    pub name: String,
+    // Physical size of the display area in millimetres
+    pub physical_size: (u32, u32),
+    // Position on the virtual desktop, in screen coordinates
+    pub position: (i32, i32),
+    // Area not covered by task bars and docks, as (x, y, width, height)
+    pub work_area: (i32, i32, u32, u32),
+    pub content_scale: (f32, f32),
+    pub current_mode: Option<VideoMode>,
+    pub video_modes: Vec<VideoMode>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WindowMode {
+    #[default]
+    Windowed,
+    // Undecorated window covering the monitor, keeps the desktop video mode
+    BorderlessFullscreen { monitor: usize },
+    // Takes over the monitor. Without a video mode the current one of the monitor is used.
+    ExclusiveFullscreen { monitor: usize, video_mode: Option<VideoMode> },
+}
@@ -0,0 +1,223 @@
+// Backend neutral description of a window to create
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ClientApi {
+    // No context is created, required for Vulkan
+    #[default]
+    NoApi,
+    OpenGl { major: u32, minor: u32 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fullscreen {
+    PrimaryMonitor,
+    // Index into the list of connected monitors
+    Monitor(usize),
+}
+
+// State of a window as reported by the backend
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct WindowState {
+    pub minimized: bool,
+    pub maximized: bool,
+    pub visible: bool,
+    pub focused: bool,
+}
+
+// Sizes of a window on a HiDPI aware backend. The logical size is in screen
+// coordinates (the unit of window positions and cursor events), the framebuffer
+// size in pixels. The content scale is the UI scale the platform asks for, which
+// may differ from the pixel ratio (e.g. 1.0 pixel ratio with 1.5 scale on Windows).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WindowMetrics {
+    pub logical_size: (u32, u32),
+    pub framebuffer_size: (u32, u32),
+    pub content_scale: (f32, f32),
+}
+
+impl WindowMetrics {
+    // Framebuffer pixels per screen coordinate, horizontally and vertically
+    pub fn pixel_ratio(&self) -> (f64, f64) {
+        let ratio = |pixels: u32, logical: u32| {
+            if logical == 0 { 1.0 } else { pixels as f64 / logical as f64 }
+        };
+        (
+            ratio(self.framebuffer_size.0, self.logical_size.0),
+            ratio(self.framebuffer_size.1, self.logical_size.1),
+        )
+    }
+
+    // Converts a position in screen coordinates (e.g. the cursor) to framebuffer pixels
+    pub fn logical_to_physical(&self, x: f64, y: f64) -> (f64, f64) {
+        let (ratio_x, ratio_y) = self.pixel_ratio();
+        (x * ratio_x, y * ratio_y)
+    }
+
+    pub fn physical_to_logical(&self, x: f64, y: f64) -> (f64, f64) {
+        let (ratio_x, ratio_y) = self.pixel_ratio();
+        (x / ratio_x, y / ratio_y)
+    }
+
+    // Size of the window in UI units, for laying out interfaces at the platform scale
+    pub fn scaled_size(&self) -> (f32, f32) {
+        // Some platforms report 0 while a window is minimized or between monitors
+        let scale = |scale: f32| if scale > 0.0 { scale } else { 1.0 };
+        (
+            self.framebuffer_size.0 as f32 / scale(self.content_scale.0),
+            self.framebuffer_size.1 as f32 / scale(self.content_scale.1),
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct WindowDescriptor {
+    pub title: String,
+    pub width: u32,
+    pub height: u32,
+
+    pub resizable: bool,
+    pub decorated: bool,
+    pub transparent: bool,
+    pub floating: bool,
+    pub maximized: bool,
+    pub visible: bool,
+    pub scale_to_monitor: bool,
+
+    pub position: Option<(i32, i32)>,
+    pub min_size: Option<(u32, u32)>,
+    pub max_size: Option<(u32, u32)>,
+    pub aspect_ratio: Option<(u32, u32)>,
+    pub fullscreen: Option<Fullscreen>,
+    pub client_api: ClientApi,
+}
+
+impl WindowDescriptor {
+    pub fn new(title: &str, width: u32, height: u32) -> Self {
+        Self {
+            title: title.to_string(),
+            width,
+            height,
+
+            resizable: true,
+            decorated: true,
+            transparent: false,
+            floating: false,
+            maximized: false,
+            visible: true,
+            scale_to_monitor: false,
+
+            position: None,
+            min_size: None,
+            max_size: None,
+            aspect_ratio: None,
+            fullscreen: None,
+            client_api: ClientApi::default(),
+        }
+    }
+
+    pub fn resizable(mut self, resizable: bool) -> Self {
+        self.resizable = resizable;
+        self
+    }
+
+    pub fn decorated(mut self, decorated: bool) -> Self {
+        self.decorated = decorated;
+        self
+    }
+
+    // Lets the desktop show through pixels with an alpha below 1.0
+    pub fn transparent(mut self, transparent: bool) -> Self {
+        self.transparent = transparent;
+        self
+    }
+
+    // Keeps the window above other windows
+    pub fn floating(mut self, floating: bool) -> Self {
+        self.floating = floating;
+        self
+    }
+
+    pub fn maximized(mut self, maximized: bool) -> Self {
+        self.maximized = maximized;
+        self
+    }
+
+    pub fn visible(mut self, visible: bool) -> Self {
+        self.visible = visible;
+        self
+    }
+
+    // Treats width and height as sizes at a content scale of 1.0 and scales them to the
+    // monitor the window is created on. Only has an effect where screen coordinates
+    // aren't already scaled (Windows, X11).
+    pub fn scale_to_monitor(mut self, scale_to_monitor: bool) -> Self {
+        self.scale_to_monitor = scale_to_monitor;
+        self
+    }
+
+    // Position of the top left corner of the content area, in screen coordinates
+    pub fn position(mut self, x: i32, y: i32) -> Self {
+        self.position = Some((x, y));
+        self
+    }
+
+    pub fn min_size(mut self, width: u32, height: u32) -> Self {
+        self.min_size = Some((width, height));
+        self
+    }
+
+    pub fn max_size(mut self, width: u32, height: u32) -> Self {
+        self.max_size = Some((width, height));
+        self
+    }
+
+    // Keeps the content area at numerator:denominator while resizing
+    pub fn aspect_ratio(mut self, numerator: u32, denominator: u32) -> Self {
+        self.aspect_ratio = Some((numerator, denominator));
+        self
+    }
+
+    pub fn fullscreen(mut self, fullscreen: Fullscreen) -> Self {
+        self.fullscreen = Some(fullscreen);
+        self
+    }
+
+    pub fn client_api(mut self, client_api: ClientApi) -> Self {
+        self.client_api = client_api;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metrics(logical_size: (u32, u32), framebuffer_size: (u32, u32), content_scale: (f32, f32)) -> WindowMetrics {
+        WindowMetrics { logical_size, framebuffer_size, content_scale }
+    }
+
+    #[test]
+    fn converts_between_logical_and_physical() {
+        let hidpi = metrics((100, 50), (200, 100), (2.0, 2.0));
+
+        assert_eq!(hidpi.pixel_ratio(), (2.0, 2.0));
+        assert_eq!(hidpi.logical_to_physical(10.0, 5.0), (20.0, 10.0));
+        assert_eq!(hidpi.physical_to_logical(20.0, 10.0), (10.0, 5.0));
+    }
+
+    #[test]
+    fn zero_logical_size_has_a_unit_pixel_ratio() {
+        assert_eq!(metrics((0, 0), (0, 0), (1.0, 1.0)).pixel_ratio(), (1.0, 1.0));
+    }
+
+    #[test]
+    fn scaled_size_divides_by_the_content_scale() {
+        assert_eq!(metrics((100, 100), (100, 100), (1.5, 1.5)).scaled_size(), (100.0 / 1.5, 100.0 / 1.5));
+    }
+
+    #[test]
+    fn scaled_size_ignores_invalid_scales() {
+        assert_eq!(metrics((80, 60), (80, 60), (0.0, -2.0)).scaled_size(), (80.0, 60.0));
+        assert_eq!(metrics((80, 60), (80, 60), (f32::NAN, 1.0)).scaled_size(), (80.0, 60.0));
+    }
+}
@@ -0,0 +1,167 @@
+use crate::error::AurenError;
+use std::fmt;
+
+// Opaque handle to a window. The slot index can be chosen at creation ("named" ids),
+// the generation changes every time the slot is reused, so a handle kept after its
+// window was destroyed never addresses a newer window in the same slot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct WindowId {
+    index: usize,
+    generation: u32,
+}
+
+impl WindowId {
+    // The slot, as passed to create_window when choosing the id
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+}
+
+impl fmt::Display for WindowId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}v{}", self.index, self.generation)
+    }
+}
+
+// How far a requested id may reach past the slots in use, so a typo can't
+// make the allocator reserve gigabytes of slots
+pub const MAX_WINDOWS: usize = 4096;
+
+#[derive(Default)]
+struct Slot {
+    generation: u32,
+    alive: bool,
+}
+
+// Hands out WindowIds for a backend, tracking which ones are still alive
+#[derive(Default)]
+pub struct WindowIdAllocator {
+    slots: Vec<Slot>,
+}
+
+impl WindowIdAllocator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Takes the requested slot, or the lowest free one
+    pub fn allocate(&mut self, requested: Option<usize>) -> Result<WindowId, AurenError> {
+        let index = match requested {
+            Some(index) => {
+                if index >= self.slots.len() + MAX_WINDOWS {
+                    return Err(AurenError::WindowIdOutOfRange(index));
+                }
+                if self.slots.get(index).is_some_and(|slot| slot.alive) {
+                    return Err(AurenError::DuplicateWindowId(index));
+                }
+                index
+            }
+            None => self.slots.iter()
+                .position(|slot| !slot.alive)
+                .unwrap_or(self.slots.len()),
+        };
+
+        if index >= self.slots.len() {
+            self.slots.resize_with(index + 1, Slot::default);
+        }
+
+        let slot = &mut self.slots[index];
+        slot.alive = true;
+
+        Ok(WindowId {
+            index,
+            generation: slot.generation,
+        })
+    }
+
+    // Frees the slot for reuse. Returns false if the id was already dead.
+    pub fn free(&mut self, id: WindowId) -> bool {
+        if self.validate(id).is_err() {
+            return false;
+        }
+
+        let slot = &mut self.slots[id.index];
+        slot.alive = false;
+        slot.generation = slot.generation.wrapping_add(1);
+        true
+    }
+
+    pub fn is_alive(&self, id: WindowId) -> bool {
+        self.validate(id).is_ok()
+    }
+
+    pub fn validate(&self, id: WindowId) -> Result<(), AurenError> {
+        match self.slots.get(id.index) {
+            Some(slot) if slot.alive && slot.generation == id.generation => Ok(()),
+            Some(slot) if slot.generation > id.generation || (slot.generation == id.generation && !slot.alive) => {
+                Err(AurenError::DeadWindowHandle(id))
+            }
+            _ => Err(AurenError::UnknownWindow(id)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn allocates_the_lowest_free_slot() {
+        let mut ids = WindowIdAllocator::new();
+        let first = ids.allocate(None).unwrap();
+        let second = ids.allocate(None).unwrap();
+        assert_eq!((first.index(), second.index()), (0, 1));
+
+        assert!(ids.free(first));
+        assert_eq!(ids.allocate(None).unwrap().index(), 0);
+        assert_eq!(ids.allocate(None).unwrap().index(), 2);
+    }
+
+    #[test]
+    fn requested_slots_must_be_free() {
+        let mut ids = WindowIdAllocator::new();
+        let named = ids.allocate(Some(3)).unwrap();
+        assert_eq!(named.index(), 3);
+        assert_eq!(ids.allocate(Some(3)), Err(AurenError::DuplicateWindowId(3)));
+
+        // The slots skipped by the named id stay available
+        assert_eq!(ids.allocate(None).unwrap().index(), 0);
+    }
+
+    #[test]
+    fn reused_slots_get_a_new_generation() {
+        let mut ids = WindowIdAllocator::new();
+        let old = ids.allocate(Some(1)).unwrap();
+        assert!(ids.free(old));
+        assert!(!ids.free(old));
+
+        let new = ids.allocate(Some(1)).unwrap();
+        assert_eq!(new.generation(), old.generation() + 1);
+        assert!(ids.is_alive(new));
+        assert_eq!(ids.validate(old), Err(AurenError::DeadWindowHandle(old)));
+    }
+
+    #[test]
+    fn unknown_ids_are_not_dead_ones() {
+        let mut ids = WindowIdAllocator::new();
+        let id = ids.allocate(None).unwrap();
+        let other = WindowIdAllocator::new().allocate(Some(5)).unwrap();
+
+        assert_eq!(ids.validate(id), Ok(()));
+        assert_eq!(ids.validate(other), Err(AurenError::UnknownWindow(other)));
+    }
+
+    #[test]
+    fn rejects_ids_far_past_the_slots_in_use() {
+        let mut ids = WindowIdAllocator::new();
+
+        assert_eq!(ids.allocate(Some(usize::MAX)), Err(AurenError::WindowIdOutOfRange(usize::MAX)));
+        assert_eq!(ids.allocate(Some(1 << 40)), Err(AurenError::WindowIdOutOfRange(1 << 40)));
+        assert_eq!(ids.allocate(Some(MAX_WINDOWS)), Err(AurenError::WindowIdOutOfRange(MAX_WINDOWS)));
+        assert_eq!(ids.allocate(Some(MAX_WINDOWS - 1)).unwrap().index(), MAX_WINDOWS - 1);
+    }
+}
@@ -1,6 +1,17 @@
+pub mod error;
 pub mod framework;
 pub mod glfwvulkan_agent;
-mod interfaces;
+pub mod headless_agent;
+pub mod interfaces;
+pub mod logging;
 
-pub use framework::AurenFoxFramework;
-pub use glfwvulkan_agent::GLFWVulkanAgent;
\ No newline at end of file
+pub use error::AurenError;
+pub use framework::{AurenFoxFramework, EventSubscriber, FixedTimestep, FixedUpdate, FrameTime, GamepadDeadZones, Input, LoopMode, Plugin, RenderFn, UserCode};
+pub use framework::{WindowCallback, WindowEventCallback};
+pub use glfwvulkan_agent::{DebugErrorAction, DevicePreference, DeviceReport, DeviceSelection, GLFWVulkanAgent, QueueFamilies, VulkanDebugConfig};
+pub use headless_agent::HeadlessAgent;
+pub use interfaces::{AurenAction, AurenEvent, AurenKey, AurenModifiers, AurenMouseButton};
+pub use interfaces::{ClientApi, Fullscreen, WindowDescriptor, WindowId, WindowMetrics, WindowState};
+pub use interfaces::{Cursor, CursorImage, CursorMode, StandardCursor};
+pub use interfaces::{GamepadAxis, GamepadButton, GamepadState, HatState, JoystickState};
+pub use interfaces::{MonitorInfo, VideoMode, WindowMode};
\ No newline at end of file
@@ -0,0 +1,292 @@
+use colored::*;
+use log::{Level, LevelFilter, Log, Metadata, Record, SetLoggerError};
+use std::{
+    collections::VecDeque,
+    fs::File,
+    io::Write,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+// Log targets used by the framework. Filter on these to silence a subsystem.
+pub mod target {
+    pub const FRAMEWORK: &str = "aurenfox::framework";
+    pub const WINDOW: &str = "aurenfox::window";
+    pub const VULKAN: &str = "aurenfox::vulkan";
+    // Messages of the Vulkan validation layer and driver
+    pub const VALIDATION: &str = "aurenfox::vulkan::validation";
+    pub const SWAPCHAIN: &str = "aurenfox::swapchain";
+}
+
+// Owned copy of a log message, as handed to every sink
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogEntry {
+    pub level: Level,
+    pub target: String,
+    pub message: String,
+}
+
+pub trait LogSink: Send + Sync {
+    fn write(&self, entry: &LogEntry);
+
+    fn flush(&self) {}
+}
+
+// Structures
+
+// Routes `log` records to sinks, with a default level and per target overrides
+pub struct AurenLogger {
+    default_level: LevelFilter,
+    target_levels: Vec<(String, LevelFilter)>,
+    sinks: Vec<Box<dyn LogSink>>,
+}
+
+// Prints the AurenFox banner followed by the subsystem and level to stderr
+pub struct ColoredStderrSink;
+
+// Appends plain text lines to a file
+pub struct FileSink {
+    file: Mutex<File>,
+}
+
+// Keeps the latest entries in memory. Clones share the same buffer,
+// so a test can keep one and hand the other to the logger.
+#[derive(Clone)]
+pub struct RingBufferSink {
+    capacity: usize,
+    entries: Arc<Mutex<VecDeque<LogEntry>>>,
+}
+
+// Implementations
+
+impl Default for AurenLogger {
+    fn default() -> Self {
+        Self {
+            default_level: LevelFilter::Info,
+            target_levels: Vec::new(),
+            sinks: Vec::new(),
+        }
+    }
+}
+
+impl AurenLogger {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn level(mut self, level: LevelFilter) -> Self {
+        self.default_level = level;
+        self
+    }
+
+    // Overrides the level for a target and everything below it, e.g. `target::SWAPCHAIN`
+    pub fn target_level(mut self, target: &str, level: LevelFilter) -> Self {
+        self.target_levels.retain(|(t, _)| t != target);
+        self.target_levels.push((target.to_string(), level));
+        self
+    }
+
+    pub fn sink(mut self, sink: Box<dyn LogSink>) -> Self {
+        self.sinks.push(sink);
+        self
+    }
+
+    // Installs the logger for the `log` facade. Fails if a logger was already installed.
+    pub fn init(self) -> Result<(), SetLoggerError> {
+        let max_level = self.target_levels
+            .iter()
+            .map(|(_, level)| *level)
+            .fold(self.default_level, |max, level| max.max(level));
+
+        log::set_boxed_logger(Box::new(self))?;
+        log::set_max_level(max_level);
+        Ok(())
+    }
+
+    fn level_for(&self, target: &str) -> LevelFilter {
+        // The longest matching target is the most specific one
+        self.target_levels
+            .iter()
+            .filter(|(t, _)| target == t || target.strip_prefix(t.as_str()).is_some_and(|rest| rest.starts_with("::")))
+            .max_by_key(|(t, _)| t.len())
+            .map(|(_, level)| *level)
+            .unwrap_or(self.default_level)
+    }
+}
+
+impl Log for AurenLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level_for(metadata.target())
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let entry = LogEntry {
+            level: record.level(),
+            target: record.target().to_string(),
+            message: record.args().to_string(),
+        };
+
+        for sink in &self.sinks {
+            sink.write(&entry);
+        }
+    }
+
+    fn flush(&self) {
+        for sink in &self.sinks {
+            sink.flush();
+        }
+    }
+}
+
+impl LogSink for ColoredStderrSink {
+    fn write(&self, entry: &LogEntry) {
+        let subsystem = match entry.target.as_str() {
+            target::FRAMEWORK => "Framework",
+            target::WINDOW => "Window",
+            target::VULKAN => "Vulkan",
+            target::VALIDATION => "Validation",
+            target::SWAPCHAIN => "Swapchain",
+            other => other,
+        };
+
+        let level = match entry.level {
+            Level::Error => "[err]".bright_white().on_red().bold(),
+            Level::Warn => "[warn]".bright_yellow(),
+            Level::Info => "[info]".bright_cyan(),
+            Level::Debug => "[debug]".white(),
+            Level::Trace => "[trace]".dimmed(),
+        };
+
+        eprintln!("{}{}->{} {} {}",
+            "Auren".bright_cyan().bold(),
+            "Fox".bright_red(),
+            subsystem.on_red(),
+            level,
+            entry.message
+        );
+    }
+}
+
+impl FileSink {
+    pub fn new<P: AsRef<Path>>(path: P) -> std::io::Result<Self> {
+        let file = File::options().create(true).append(true).open(path)?;
+        Ok(Self {
+            file: Mutex::new(file),
+        })
+    }
+}
+
+impl LogSink for FileSink {
+    fn write(&self, entry: &LogEntry) {
+        if let Ok(mut file) = self.file.lock() {
+            let _ = writeln!(file, "[{}] {}: {}", entry.level, entry.target, entry.message);
+        }
+    }
+
+    fn flush(&self) {
+        if let Ok(mut file) = self.file.lock() {
+            let _ = file.flush();
+        }
+    }
+}
+
+impl RingBufferSink {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: Arc::new(Mutex::new(VecDeque::with_capacity(capacity))),
+        }
+    }
+
+    // Oldest entry first
+    pub fn entries(&self) -> Vec<LogEntry> {
+        self.entries.lock().map(|e| e.iter().cloned().collect()).unwrap_or_default()
+    }
+
+    pub fn clear(&self) {
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.clear();
+        }
+    }
+}
+
+impl LogSink for RingBufferSink {
+    fn write(&self, entry: &LogEntry) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        if let Ok(mut entries) = self.entries.lock() {
+            if entries.len() == self.capacity {
+                entries.pop_front();
+            }
+            entries.push_back(entry.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log(logger: &AurenLogger, level: Level, target: &str, message: &str) {
+        logger.log(&Record::builder().level(level).target(target).args(format_args!("{}", message)).build());
+    }
+
+    #[test]
+    fn level_for_picks_the_most_specific_target() {
+        let logger = AurenLogger::new()
+            .level(LevelFilter::Info)
+            .target_level(target::VULKAN, LevelFilter::Warn)
+            .target_level(target::VALIDATION, LevelFilter::Trace);
+
+        assert_eq!(logger.level_for(target::FRAMEWORK), LevelFilter::Info);
+        assert_eq!(logger.level_for(target::VULKAN), LevelFilter::Warn);
+        assert_eq!(logger.level_for(target::VALIDATION), LevelFilter::Trace);
+        assert_eq!(logger.level_for("aurenfox::vulkan::device"), LevelFilter::Warn);
+        // Only whole path segments match
+        assert_eq!(logger.level_for("aurenfox::vulkanx"), LevelFilter::Info);
+    }
+
+    #[test]
+    fn target_level_replaces_an_earlier_override() {
+        let logger = AurenLogger::new()
+            .target_level(target::SWAPCHAIN, LevelFilter::Off)
+            .target_level(target::SWAPCHAIN, LevelFilter::Debug);
+
+        assert_eq!(logger.level_for(target::SWAPCHAIN), LevelFilter::Debug);
+    }
+
+    #[test]
+    fn ring_buffer_keeps_the_latest_entries() {
+        let ring = RingBufferSink::new(2);
+        let logger = AurenLogger::new()
+            .level(LevelFilter::Debug)
+            .target_level(target::SWAPCHAIN, LevelFilter::Warn)
+            .sink(Box::new(ring.clone()));
+
+        log(&logger, Level::Info, target::SWAPCHAIN, "filtered");
+        log(&logger, Level::Debug, target::WINDOW, "first");
+        log(&logger, Level::Info, target::FRAMEWORK, "second");
+        log(&logger, Level::Error, target::SWAPCHAIN, "third");
+
+        let messages: Vec<String> = ring.entries().into_iter().map(|entry| entry.message).collect();
+        assert_eq!(messages, ["second", "third"]);
+        assert_eq!(ring.entries()[1].target, target::SWAPCHAIN);
+
+        ring.clear();
+        assert!(ring.entries().is_empty());
+    }
+
+    #[test]
+    fn empty_ring_buffer_stores_nothing() {
+        let ring = RingBufferSink::new(0);
+        ring.write(&LogEntry { level: Level::Error, target: target::VULKAN.to_string(), message: "lost".to_string() });
+
+        assert!(ring.entries().is_empty());
+    }
+}
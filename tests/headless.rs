@@ -0,0 +1,211 @@
+use aurenfox::{AurenAction, AurenEvent, AurenFoxFramework, AurenKey, AurenModifiers, Cursor, CursorImage, CursorMode};
+use aurenfox::{HeadlessAgent, LoopMode, WindowDescriptor};
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+#[test]
+fn frame_limit_ends_the_loop() {
+    let agent = HeadlessAgent::new().with_frame_limit(3);
+    let handle = agent.handle();
+    let mut app = AurenFoxFramework::new(agent);
+    app.create_window("main", 64, 64, None).unwrap();
+
+    let frames = Rc::new(Cell::new(0));
+    let counter = frames.clone();
+    app.run(Some(Box::new(move |_| counter.set(counter.get() + 1))));
+
+    assert_eq!(frames.get(), 3);
+    assert_eq!(handle.frame_index(), 3);
+    assert!(handle.is_finished());
+}
+
+#[test]
+fn closing_the_master_window_ends_the_loop() {
+    let agent = HeadlessAgent::new().with_frame_limit(100);
+    let handle = agent.handle();
+    let mut app = AurenFoxFramework::new(agent);
+    app.create_window("first", 64, 64, None).unwrap();
+    let master = app.create_window("master", 64, 64, None).unwrap();
+    app.assign_master(master).unwrap();
+    handle.close_at_frame(3, master);
+
+    let frames = Rc::new(Cell::new(0));
+    let counter = frames.clone();
+    app.run(Some(Box::new(move |app| {
+        // The frame delivering CloseRequested is the last one to run
+        assert!(app.is_window_alive(master));
+        counter.set(counter.get() + 1);
+    })));
+
+    assert_eq!(frames.get(), 4);
+    assert_eq!(handle.frame_index(), 4);
+    assert!(handle.is_finished());
+}
+
+#[test]
+fn requested_ids_must_be_free() {
+    let mut app = AurenFoxFramework::new(HeadlessAgent::new());
+    let window = app.create_window("a", 64, 64, Some(1)).unwrap();
+
+    assert_eq!(window.index(), 1);
+    assert!(app.create_window("b", 64, 64, Some(1)).is_err());
+    assert!(app.create_window("c", 64, 64, None).is_ok());
+}
+
+#[test]
+fn queued_windows_are_destroyed_before_the_next_frame() {
+    let agent = HeadlessAgent::new().with_frame_limit(3);
+    let handle = agent.handle();
+    let mut app = AurenFoxFramework::new(agent);
+    let doomed = app.create_window("doomed", 64, 64, None).unwrap();
+    let kept = app.create_window("kept", 64, 64, None).unwrap();
+
+    let alive = Rc::new(RefCell::new(Vec::new()));
+    let log = alive.clone();
+    let frame_handle = handle.clone();
+    app.run(Some(Box::new(move |app| {
+        log.borrow_mut().push(frame_handle.has_window(doomed));
+        if app.time().frame_index() == 0 {
+            app.queue_destroy(doomed).unwrap();
+            // Still there for the rest of the frame it was queued in
+            assert!(app.is_window_alive(doomed));
+        }
+    })));
+
+    assert_eq!(*alive.borrow(), [true, false, false]);
+    assert!(handle.has_window(kept));
+    assert_eq!(handle.window_count(), 1);
+}
+
+#[test]
+fn events_reach_subscribers_and_the_frame() {
+    let agent = HeadlessAgent::new().with_frame_limit(3);
+    let handle = agent.handle();
+    let mut app = AurenFoxFramework::new(agent);
+    let window = app.create_window("main", 64, 64, None).unwrap();
+
+    let subscribed = Rc::new(RefCell::new(Vec::new()));
+    let sink = subscribed.clone();
+    app.subscribe(Box::new(move |event| sink.borrow_mut().push(event.clone())));
+
+    let resize = AurenEvent::Resized { window, time: 0.0, width: 32, height: 16 };
+    handle.push_event(resize.clone());
+
+    let per_frame = Rc::new(RefCell::new(Vec::new()));
+    let counts = per_frame.clone();
+    app.run(Some(Box::new(move |app| counts.borrow_mut().push(app.events().count()))));
+
+    assert_eq!(*subscribed.borrow(), vec![resize]);
+    assert_eq!(per_frame.borrow()[0], 1);
+    assert!(per_frame.borrow()[1..].iter().all(|&count| count == 0));
+}
+
+#[test]
+fn destroyed_windows_leave_no_input_behind() {
+    let agent = HeadlessAgent::new().with_frame_limit(3);
+    let handle = agent.handle();
+    let mut app = AurenFoxFramework::new(agent);
+    let doomed = app.create_window("doomed", 64, 64, None).unwrap();
+    app.create_window("kept", 64, 64, None).unwrap();
+
+    handle.push_event(AurenEvent::Focused { window: doomed, time: 0.0, focused: true });
+    handle.push_event(AurenEvent::Key {
+        window: doomed,
+        time: 0.0,
+        key: AurenKey::W,
+        scancode: 0,
+        action: AurenAction::Press,
+        modifiers: AurenModifiers::default(),
+    });
+
+    app.run(Some(Box::new(move |app| {
+        if app.time().frame_index() == 0 {
+            assert!(app.input().is_key_down_in(doomed, AurenKey::W));
+            app.queue_destroy(doomed).unwrap();
+        } else {
+            assert!(!app.input().is_key_down(AurenKey::W));
+            assert_eq!(app.input().focused_window(), None);
+        }
+    })));
+}
+
+#[test]
+fn reinitialize_kills_old_handles() {
+    let mut app = AurenFoxFramework::new(HeadlessAgent::new());
+    let old = app.create_window("old", 64, 64, Some(0)).unwrap();
+
+    app.backend.reinitialize().unwrap();
+
+    assert!(!app.is_window_alive(old));
+    let new = app.create_window("new", 64, 64, Some(0)).unwrap();
+    assert_ne!(old, new);
+}
+
+#[test]
+fn reactive_mode_only_runs_frames_with_work() {
+    let agent = HeadlessAgent::new().with_frame_limit(10);
+    let handle = agent.handle();
+    let mut app = AurenFoxFramework::new(agent);
+    let window = app.create_window("main", 64, 64, None).unwrap();
+    app.set_loop_mode(LoopMode::Reactive(None));
+    handle.push_event(AurenEvent::Focused { window, time: 0.0, focused: true });
+
+    let runs = Rc::new(Cell::new(0));
+    let counter = runs.clone();
+    app.run(Some(Box::new(move |app| {
+        counter.set(counter.get() + 1);
+        if counter.get() == 1 {
+            app.request_redraw(window).unwrap();
+        } else {
+            assert!(app.is_redraw_requested(window));
+        }
+    })));
+
+    // The focus event, then the requested redraw
+    assert_eq!(runs.get(), 2);
+}
+
+#[test]
+fn descriptors_configure_new_windows() {
+    let agent = HeadlessAgent::new();
+    let handle = agent.handle();
+    let mut app = AurenFoxFramework::new(agent);
+    let descriptor = WindowDescriptor::new("described", 320, 240).position(10, 20).maximized(true);
+
+    let window = app.create_window_with(&descriptor, None).unwrap();
+
+    assert_eq!(handle.window_title(window).as_deref(), Some("described"));
+    assert_eq!(handle.window_position(window), Some((10, 20)));
+    assert!(handle.window_state(window).unwrap().maximized);
+}
+
+#[test]
+fn cursors_are_set_per_window() {
+    let agent = HeadlessAgent::new();
+    let handle = agent.handle();
+    let mut app = AurenFoxFramework::new(agent);
+    let window = app.create_window("main", 64, 64, None).unwrap();
+
+    app.set_cursor_mode(window, CursorMode::Captured).unwrap();
+    assert_eq!(app.cursor_mode(window).unwrap(), CursorMode::Captured);
+
+    let image = CursorImage::new(2, 2, vec![255; 16], 1, 1).unwrap();
+    app.set_cursor(window, &Cursor::Custom(image.clone())).unwrap();
+    assert_eq!(handle.cursor(window), Some(Cursor::Custom(image)));
+}
+
+#[test]
+fn typed_text_arrives_on_the_next_frame() {
+    let agent = HeadlessAgent::new().with_frame_limit(2);
+    let handle = agent.handle();
+    let mut app = AurenFoxFramework::new(agent);
+    let window = app.create_window("main", 64, 64, None).unwrap();
+    handle.type_text(window, "héllo");
+
+    let frames = Rc::new(RefCell::new(Vec::new()));
+    let texts = frames.clone();
+    app.run(Some(Box::new(move |app| texts.borrow_mut().push(app.input().text_input_in(window).to_string()))));
+
+    assert_eq!(frames.borrow()[0], "héllo");
+    assert_eq!(frames.borrow()[1], "");
+}
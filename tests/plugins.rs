@@ -0,0 +1,95 @@
+use aurenfox::{AurenFoxFramework, HeadlessAgent, Plugin};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+type Log = Rc<RefCell<Vec<String>>>;
+
+// Records every hook it receives as "<hook> <name>"
+struct Recorder {
+    name: &'static str,
+    log: Log,
+}
+
+impl Recorder {
+    fn new(name: &'static str, log: &Log) -> Self {
+        Self { name, log: log.clone() }
+    }
+
+    fn record(&self, hook: &str) {
+        self.log.borrow_mut().push(format!("{} {}", hook, self.name));
+    }
+}
+
+impl Plugin for Recorder {
+    fn build(&mut self, _app: &mut AurenFoxFramework) {
+        self.record("build");
+    }
+
+    fn startup(&mut self, _app: &mut AurenFoxFramework) {
+        self.record("startup");
+    }
+
+    fn pre_frame(&mut self, _app: &mut AurenFoxFramework) {
+        self.record("pre_frame");
+    }
+
+    fn post_frame(&mut self, _app: &mut AurenFoxFramework) {
+        self.record("post_frame");
+    }
+
+    fn shutdown(&mut self, _app: &mut AurenFoxFramework) {
+        self.record("shutdown");
+    }
+}
+
+fn single_frame_app() -> AurenFoxFramework {
+    let mut app = AurenFoxFramework::new(HeadlessAgent::new().with_frame_limit(1));
+    app.create_window("main", 64, 64, None).unwrap();
+    app
+}
+
+#[test]
+fn hooks_nest_in_the_order_plugins_were_added() {
+    let log = Log::default();
+    let mut app = single_frame_app();
+    app.add_plugin(Recorder::new("a", &log));
+    app.add_plugin(Recorder::new("b", &log));
+
+    let user = log.clone();
+    app.run(Some(Box::new(move |_| user.borrow_mut().push("user".to_string()))));
+
+    assert_eq!(*log.borrow(), [
+        "build a", "build b",
+        "startup a", "startup b",
+        "pre_frame a", "pre_frame b",
+        "user",
+        "post_frame b", "post_frame a",
+        "shutdown b", "shutdown a",
+    ]);
+}
+
+#[test]
+fn plugins_added_while_running_start_immediately() {
+    let log = Log::default();
+    let mut app = single_frame_app();
+    app.add_plugin(Recorder::new("a", &log));
+
+    let added = log.clone();
+    app.run(Some(Box::new(move |app| app.add_plugin(Recorder::new("late", &added)))));
+
+    assert_eq!(*log.borrow(), [
+        "build a",
+        "startup a",
+        "pre_frame a",
+        "build late", "startup late",
+        "post_frame late", "post_frame a",
+        "shutdown late", "shutdown a",
+    ]);
+}
+
+#[test]
+fn default_name_is_the_type_name() {
+    let recorder = Recorder::new("a", &Log::default());
+
+    assert!(recorder.name().ends_with("Recorder"));
+}
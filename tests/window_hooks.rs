@@ -0,0 +1,122 @@
+use aurenfox::headless_agent::HeadlessHandle;
+use aurenfox::{AurenEvent, AurenFoxFramework, HeadlessAgent};
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+// Counts how many values were dropped
+struct Tracked(Rc<Cell<u32>>);
+
+impl Drop for Tracked {
+    fn drop(&mut self) {
+        self.0.set(self.0.get() + 1);
+    }
+}
+
+fn app(frames: u64) -> (AurenFoxFramework, HeadlessHandle) {
+    let agent = HeadlessAgent::new().with_frame_limit(frames);
+    let handle = agent.handle();
+    (AurenFoxFramework::new(agent), handle)
+}
+
+#[test]
+fn data_is_typed_per_window() {
+    let (mut app, _) = app(1);
+    let first = app.create_window("first", 64, 64, None).unwrap();
+    let second = app.create_window("second", 64, 64, None).unwrap();
+
+    assert_eq!(app.set_window_data(first, 1u32).unwrap(), None);
+    assert_eq!(app.set_window_data(first, 2u32).unwrap(), Some(1));
+    app.set_window_data(first, "label").unwrap();
+
+    *app.window_data_mut::<u32>(first).unwrap() += 1;
+    assert_eq!(app.window_data::<u32>(first), Some(&3));
+    assert_eq!(app.window_data::<&str>(first), Some(&"label"));
+    assert_eq!(app.window_data::<u32>(second), None);
+
+    assert_eq!(app.take_window_data::<u32>(first), Some(3));
+    assert_eq!(app.window_data::<u32>(first), None);
+}
+
+#[test]
+fn hooks_run_in_order_for_their_window() {
+    let (mut app, handle) = app(1);
+    let window = app.create_window("main", 64, 64, None).unwrap();
+    let other = app.create_window("other", 64, 64, None).unwrap();
+    handle.push_event(AurenEvent::Focused { window, time: 0.0, focused: true });
+    handle.push_event(AurenEvent::Focused { window: other, time: 0.0, focused: true });
+
+    let calls = Rc::new(RefCell::new(Vec::new()));
+    let log = calls.clone();
+    app.on_window_event(window, Box::new(move |app, id, event| {
+        assert_eq!(event.window(), Some(id));
+        // The whole frame stays readable while the callbacks run
+        assert_eq!(app.events().count(), 2);
+        log.borrow_mut().push("event");
+    })).unwrap();
+    let log = calls.clone();
+    app.on_window_update(window, Box::new(move |_, _| log.borrow_mut().push("update"))).unwrap();
+    let log = calls.clone();
+    app.on_window_draw(window, Box::new(move |_, _| log.borrow_mut().push("draw"))).unwrap();
+
+    let log = calls.clone();
+    app.run(Some(Box::new(move |app| {
+        assert_eq!(app.events().count(), 2);
+        log.borrow_mut().push("run");
+    })));
+
+    assert_eq!(*calls.borrow(), ["event", "update", "run", "draw"]);
+}
+
+#[test]
+fn hooks_and_data_are_dropped_on_destroy() {
+    let (mut app, handle) = app(3);
+    let doomed = app.create_window("doomed", 64, 64, None).unwrap();
+    app.create_window("kept", 64, 64, None).unwrap();
+
+    let drops = Rc::new(Cell::new(0));
+    app.set_window_data(doomed, Tracked(drops.clone())).unwrap();
+    let hook = Tracked(drops.clone());
+    let draws = Rc::new(Cell::new(0));
+    let counter = draws.clone();
+    app.on_window_draw(doomed, Box::new(move |_, _| {
+        let _ = &hook;
+        counter.set(counter.get() + 1);
+    })).unwrap();
+
+    // Destroyed from its own event callback
+    app.on_window_event(doomed, Box::new(|app, id, _| app.queue_destroy(id).unwrap())).unwrap();
+    handle.push_event(AurenEvent::Focused { window: doomed, time: 0.0, focused: true });
+
+    app.run(None);
+
+    assert_eq!(draws.get(), 1);
+    assert_eq!(drops.get(), 2);
+}
+
+#[test]
+fn hooks_and_data_are_dropped_on_close() {
+    let (mut app, handle) = app(4);
+    let closed = app.create_window("closed", 64, 64, None).unwrap();
+    app.create_window("kept", 64, 64, None).unwrap();
+
+    let drops = Rc::new(Cell::new(0));
+    app.set_window_data(closed, Tracked(drops.clone())).unwrap();
+    let updates = Rc::new(RefCell::new(Vec::new()));
+    let log = updates.clone();
+    app.on_window_update(closed, Box::new(move |app, _| log.borrow_mut().push(app.time().frame_index()))).unwrap();
+    handle.close_at_frame(1, closed);
+
+    let with_data = Rc::new(RefCell::new(Vec::new()));
+    let log = with_data.clone();
+    app.run(Some(Box::new(move |app| {
+        if app.window_data::<Tracked>(closed).is_some() {
+            log.borrow_mut().push(app.time().frame_index());
+        }
+    })));
+
+    // Still there in the frame that delivers CloseRequested, gone from the next one
+    assert_eq!(*with_data.borrow(), [0, 1]);
+    assert_eq!(*updates.borrow(), [0, 1]);
+    assert_eq!(drops.get(), 1);
+    assert!(app.set_window_data(closed, 0u8).is_err());
+}